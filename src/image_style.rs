@@ -0,0 +1,336 @@
+use crate::page::{Symbol, Theme};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Ramp {
+    #[default]
+    Short,
+    Long,
+    Custom(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Ascii,
+    HalfBlock,
+    Quadrant,
+    Braille,
+    Edges,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    #[default]
+    Source,
+    Theme,
+    Tint(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    #[default]
+    None,
+    FloydSteinberg,
+    Atkinson,
+}
+
+// glyphs run from sparse to dense, dense glyphs are used for bright pixels
+const SHORT: &str = ".:o0@";
+const LONG: &str = " .'`^\",:;Il!i><~+_-?][}{1)(|\\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$";
+
+const QUADRANTS: [char; 16] = [' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█'];
+const BRAILLE: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+const FLOYD_STEINBERG: [(isize, usize, f32); 4] = [(1, 0, 7.0 / 16.0), (-1, 1, 3.0 / 16.0), (0, 1, 5.0 / 16.0), (1, 1, 1.0 / 16.0)];
+const ATKINSON: [(isize, usize, f32); 6] = [(1, 0, 0.125), (2, 0, 0.125), (-1, 1, 0.125), (0, 1, 0.125), (1, 1, 0.125), (0, 2, 0.125)];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStyle {
+    mode: Mode,
+    ramp: Ramp,
+    gamma: f32,
+    contrast: f32,
+    invert: bool,
+    dither: Dither,
+    edge_threshold: f32,
+    palette: Palette,
+}
+
+impl Default for ImageStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImageStyle {
+    pub fn new() -> Self {
+        Self {
+            mode: Mode::Ascii,
+            ramp: Ramp::Short,
+            gamma: 1.0,
+            contrast: 1.0,
+            invert: false,
+            dither: Dither::None,
+            edge_threshold: 0.2,
+            palette: Palette::Source,
+        }
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn ramp(mut self, ramp: Ramp) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    pub fn contrast(mut self, contrast: f32) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    pub fn invert(mut self, enabled: bool) -> Self {
+        self.invert = enabled;
+        self
+    }
+
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn edge_threshold(mut self, threshold: f32) -> Self {
+        self.edge_threshold = threshold;
+        self
+    }
+
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub(crate) fn tint(&self) -> Option<&str> {
+        match &self.palette {
+            Palette::Tint(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    // maps a pixel colour onto the theme, tint is the resolved colour of Palette::Tint
+    pub(crate) fn recolor(&self, color: (f32, f32, f32), theme: &Theme, tint: (f32, f32, f32)) -> (f32, f32, f32) {
+        match self.palette {
+            Palette::Source => color,
+            Palette::Theme => [theme.fg, theme.h1, theme.h2, theme.h3, theme.h4].into_iter()
+                .min_by(|a, b| distance(*a, color).total_cmp(&distance(*b, color)))
+                .unwrap_or(color),
+            Palette::Tint(_) => {
+                let l = luminance(color);
+                let (bg, t) = (theme.bg, tint);
+                (bg.0 + (t.0 - bg.0) * l, bg.1 + (t.1 - bg.1) * l, bg.2 + (t.2 - bg.2) * l)
+            }
+        }
+    }
+
+    // pixels covered by one cell
+    pub(crate) fn cell_pixels(&self) -> (usize, usize) {
+        match self.mode {
+            Mode::Ascii | Mode::Edges => (1, 1),
+            Mode::HalfBlock => (1, 2),
+            Mode::Quadrant => (2, 2),
+            Mode::Braille => (2, 4),
+        }
+    }
+
+    pub(crate) fn symbols(&self, img: &[f32], w: usize, h: usize) -> (Vec<Symbol>, usize, usize) {
+        let (sx, sy) = self.cell_pixels();
+        let (cw, ch) = (w.div_ceil(sx), h.div_ceil(sy));
+        let pixel = |x: usize, y: usize| (x < w && y < h).then(|| {
+            let pix = (y * w + x) * 3;
+            (img[pix], img[pix + 1], img[pix + 2])
+        });
+        let brightness: Vec<f32> = img.chunks(3).take(w * h).map(|p| luminance((p[0], p[1], p[2]))).collect();
+
+        let mut symbols = Vec::with_capacity(cw * ch);
+        match self.mode {
+            Mode::Ascii => {
+                let glyphs = self.glyphs();
+                let levels = self.quantise(&brightness, w, h, glyphs.len());
+                for y in 0..h {
+                    for x in 0..w {
+                        symbols.push(symbol(glyphs[levels[y * w + x]], pixel(x, y).unwrap_or_default(), None));
+                    }
+                }
+            }
+            Mode::HalfBlock => {
+                for y in 0..ch {
+                    for x in 0..cw {
+                        let upper = pixel(x, y * 2).unwrap_or_default();
+                        symbols.push(symbol('▀', upper, pixel(x, y * 2 + 1)));
+                    }
+                }
+            }
+            Mode::Quadrant => {
+                for y in 0..ch {
+                    for x in 0..cw {
+                        let cell: Vec<_> = (0..4).filter_map(|i| pixel(x * 2 + i % 2, y * 2 + i / 2).map(|p| (i, p))).collect();
+                        let mean = cell.iter().map(|(_, p)| luminance(*p)).sum::<f32>() / cell.len() as f32;
+                        let (bright, dark): (Vec<_>, Vec<_>) = cell.iter().partition(|(_, p)| luminance(*p) > mean);
+
+                        let mask = bright.iter().fold(0, |mask, (i, _)| mask | 1 << i);
+                        let fg = average(bright.iter().map(|(_, p)| *p)).or(average(dark.iter().map(|(_, p)| *p))).unwrap_or_default();
+                        symbols.push(symbol(QUADRANTS[mask], fg, average(dark.iter().map(|(_, p)| *p))));
+                    }
+                }
+            }
+            Mode::Braille => {
+                let dots = self.quantise(&brightness, w, h, 2);
+                for y in 0..ch {
+                    for x in 0..cw {
+                        let mut bits = 0;
+                        let mut lit = Vec::new();
+                        for (dy, row) in BRAILLE.iter().enumerate() {
+                            for (dx, bit) in row.iter().enumerate() {
+                                let (px, py) = (x * 2 + dx, y * 4 + dy);
+                                if px < w && py < h && dots[py * w + px] == 1 {
+                                    bits |= bit;
+                                    lit.extend(pixel(px, py));
+                                }
+                            }
+                        }
+                        let character = char::from_u32(0x2800 + bits).unwrap_or(' ');
+                        symbols.push(symbol(character, average(lit.into_iter()).unwrap_or_default(), None));
+                    }
+                }
+            }
+            Mode::Edges => {
+                let values: Vec<f32> = brightness.iter().map(|l| self.adjust(*l)).collect();
+                let at = |x: usize, y: usize, dx: isize, dy: isize| {
+                    let x = x.saturating_add_signed(dx).min(w - 1);
+                    let y = y.saturating_add_signed(dy).min(h - 1);
+                    values[y * w + x]
+                };
+
+                for y in 0..h {
+                    for x in 0..w {
+                        let gx = at(x, y, 1, -1) + 2.0 * at(x, y, 1, 0) + at(x, y, 1, 1) - at(x, y, -1, -1) - 2.0 * at(x, y, -1, 0) - at(x, y, -1, 1);
+                        let gy = at(x, y, -1, 1) + 2.0 * at(x, y, 0, 1) + at(x, y, 1, 1) - at(x, y, -1, -1) - 2.0 * at(x, y, 0, -1) - at(x, y, 1, -1);
+                        let color = pixel(x, y).unwrap_or_default();
+
+                        // the strongest possible response on 0..1 input is 4
+                        if (gx * gx + gy * gy).sqrt() / 4.0 < self.edge_threshold {
+                            symbols.push(symbol(' ', color, None));
+                            continue;
+                        }
+
+                        // edges run across the gradient, rows grow downwards
+                        let angle = gy.atan2(gx).to_degrees().rem_euclid(180.0);
+                        let character = match angle {
+                            a if !(22.5..157.5).contains(&a) => '|',
+                            a if a < 67.5 => '/',
+                            a if a < 112.5 => if gy < 0.0 { '_' } else { '-' },
+                            _ => '\\',
+                        };
+                        symbols.push(symbol(character, color, None));
+                    }
+                }
+            }
+        }
+
+        (symbols, cw, ch)
+    }
+
+    // the block and braille characters a mode may draw, ascii ramps and edges use plain text
+    pub(crate) fn mode_chars(&self) -> Vec<char> {
+        match self.mode {
+            Mode::Ascii | Mode::Edges => Vec::new(),
+            Mode::HalfBlock => vec!['▀'],
+            Mode::Quadrant => QUADRANTS[1..].to_vec(),
+            Mode::Braille => (0x2801..=0x28ff).filter_map(char::from_u32).collect(),
+        }
+    }
+
+    pub(crate) fn glyphs(&self) -> Vec<char> {
+        let glyphs: Vec<char> = match &self.ramp {
+            Ramp::Short => SHORT.chars().collect(),
+            Ramp::Long => LONG.chars().collect(),
+            Ramp::Custom(ramp) => ramp.chars().collect(),
+        };
+
+        if glyphs.is_empty() { vec![' '] } else { glyphs }
+    }
+
+    pub(crate) fn adjust(&self, luminance: f32) -> f32 {
+        let l = ((luminance - 0.5) * self.contrast + 0.5).clamp(0.0, 1.0);
+        let l = if self.gamma > 0.0 { l.powf(1.0 / self.gamma) } else { l };
+        if self.invert { 1.0 - l } else { l }
+    }
+
+    // quantises adjusted luminance into levels buckets, diffusing the error if dithering is on
+    pub(crate) fn quantise(&self, luminance: &[f32], w: usize, h: usize, levels: usize) -> Vec<usize> {
+        let mut values: Vec<f32> = luminance.iter().map(|l| self.adjust(*l)).collect();
+        let mut indices = vec![0; values.len()];
+        let kernel: &[(isize, usize, f32)] = match self.dither {
+            Dither::None => &[],
+            Dither::FloydSteinberg => &FLOYD_STEINBERG,
+            Dither::Atkinson => &ATKINSON,
+        };
+
+        for y in 0..h {
+            for x in 0..w {
+                let value = values[y * w + x];
+                if kernel.is_empty() || levels < 2 {
+                    indices[y * w + x] = ((value * levels as f32).floor().max(0.0) as usize).min(levels - 1);
+                    continue;
+                }
+
+                let top = (levels - 1) as f32;
+                let index = (value * top).round().clamp(0.0, top);
+                indices[y * w + x] = index as usize;
+
+                let error = value - index / top;
+                for (dx, dy, weight) in kernel {
+                    let Some(nx) = x.checked_add_signed(*dx).filter(|nx| *nx < w) else { continue; };
+                    if y + dy < h {
+                        values[(y + dy) * w + nx] += error * weight;
+                    }
+                }
+            }
+        }
+
+        indices
+    }
+}
+
+fn luminance((r, g, b): (f32, f32, f32)) -> f32 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+fn distance(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)
+}
+
+fn average(pixels: impl Iterator<Item = (f32, f32, f32)>) -> Option<(f32, f32, f32)> {
+    let (mut sum, mut n) = ((0.0, 0.0, 0.0), 0.0);
+    for (r, g, b) in pixels {
+        sum = (sum.0 + r, sum.1 + g, sum.2 + b);
+        n += 1.0;
+    }
+    (n > 0.0).then(|| (sum.0 / n, sum.1 / n, sum.2 / n))
+}
+
+fn symbol(character: char, color: (f32, f32, f32), background: Option<(f32, f32, f32)>) -> Symbol {
+    Symbol {
+        character,
+        color,
+        bold: false,
+        italic: false,
+        background,
+    }
+}
@@ -1,91 +1,230 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
 use std::rc::Rc;
 
-use crate::page::Symbol;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageResult};
+
+use crate::diagnostics::{Diagnostic, Diagnostics, Severity};
+use crate::image_style::{ImageStyle, Mode};
+use crate::lines::{LineStyle, DOWN, LEFT, RIGHT, UP};
+use crate::list::{Entry, List};
+use crate::markup::{self, MarkupError};
+use crate::meter::Meter;
+use crate::page::{Symbol, Theme};
+use crate::regions::{Ownership, Region, Regions};
+use crate::stack::Stack;
+use crate::table::{Table, TableCell};
+use crate::text::{ellipsize, justify, wrap};
+use crate::timeline::Timeline;
 
 #[derive(Clone)]
 pub struct LayoutContext {
     buffer: Rc<RefCell<Vec<Vec<Symbol>>>>,
+    lines: Rc<RefCell<Vec<Vec<u8>>>>,
     view_size: (usize, usize),
     glimpse_origin: (usize, usize),
     glimpse_size: (usize, usize),
-    fg_color: (f32, f32, f32),
-    h1_color: (f32, f32, f32),
-    h2_color: (f32, f32, f32),
-    h3_color: (f32, f32, f32),
-    h4_color: (f32, f32, f32),
+    theme: Rc<Theme>,
+    page: usize,
+    pages: Rc<Cell<usize>>,
+    flow: Option<Flow>,
+    unbounded: Option<usize>,
+    measure: Option<Measure>,
+    hyphenate: bool,
+    line_style: LineStyle,
+    overflow: OverflowPolicy,
+    path: Rc<str>,
+    diagnostics: Rc<RefCell<Diagnostics>>,
+    region: usize,
+    regions: Rc<RefCell<Regions>>,
+    ownership: Option<Rc<RefCell<Ownership>>>,
+    aspect: f32,
+    glyphs: Option<Rc<HashSet<char>>>,
+    image_style: ImageStyle,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    #[default]
+    Clip,
+    Ellipsis,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub hidden_lines: usize,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fit {
+    First,
+    Second,
+}
+
+#[derive(Clone)]
+struct Measure {
+    origin: (usize, usize),
+    extent: Rc<Cell<(usize, usize)>>,
+}
+
+#[derive(Clone, Copy)]
+struct Flow {
+    top: usize,
+    height: usize,
+}
+
+enum TableRow {
+    Rule(u8),
+    Cells(Vec<(usize, Vec<Symbol>)>),
+}
+
+const FLOW_HEIGHT: usize = usize::MAX / 4;
+const MAX_FLOW_PAGES: usize = 1000;
+
 impl LayoutContext {
-    pub fn new(buffer: Rc<RefCell<Vec<Vec<Symbol>>>>, view_size: (usize, usize), glimpse_origin: (usize, usize), glimpse_size: (usize, usize), fg_color: (f32, f32, f32), h1_color: (f32, f32, f32), h2_color: (f32, f32, f32), h3_color: (f32, f32, f32), h4_color: (f32, f32, f32)) -> Self {
-        Self { buffer, view_size, glimpse_origin, glimpse_size, fg_color, h1_color, h2_color, h3_color, h4_color }
+    pub fn new(buffer: Rc<RefCell<Vec<Vec<Symbol>>>>, view_size: (usize, usize), glimpse_origin: (usize, usize), glimpse_size: (usize, usize), theme: Rc<Theme>, page: usize, pages: Rc<Cell<usize>>) -> Self {
+        let path: Rc<str> = Rc::from("page");
+        let mut regions = Regions::default();
+        let region = regions.register(Region { parent: None, path: path.clone(), visible: Some((glimpse_origin, glimpse_size)) });
+
+        Self {
+            buffer,
+            lines: Rc::new(RefCell::new(vec![vec![0; view_size.0]; view_size.1])),
+            view_size,
+            glimpse_origin,
+            glimpse_size,
+            theme,
+            page,
+            pages,
+            flow: None,
+            unbounded: None,
+            measure: None,
+            hyphenate: false,
+            line_style: LineStyle::Ascii,
+            overflow: OverflowPolicy::Clip,
+            path,
+            diagnostics: Rc::new(RefCell::new(Diagnostics::default())),
+            region,
+            regions: Rc::new(RefCell::new(regions)),
+            ownership: None,
+            aspect: 2.0,
+            glyphs: None,
+            image_style: ImageStyle::new(),
+        }
+    }
+
+    pub(crate) fn regions(&self) -> Rc<RefCell<Regions>> {
+        self.regions.clone()
     }
 
+    pub(crate) fn detect_overwrites(&mut self) {
+        self.ownership = Some(Rc::new(RefCell::new(Ownership::new(self.view_size.0, self.view_size.1))));
+    }
+
+    pub(crate) fn font_glyphs(&mut self, glyphs: Rc<HashSet<char>>) {
+        self.glyphs = Some(glyphs);
+    }
+
+    pub(crate) fn symbol_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    #[allow(clippy::misnamed_getters)]
     pub fn view_size(&self) -> (usize, usize){
-        return self.glimpse_size;
+        self.glimpse_size
     }
-}
 
-impl LayoutContext {
-    pub fn frame(&mut self, inner: fn(LayoutContext)) {
-        if self.glimpse_size.0 < 2 || self.glimpse_size.1 < 2 { return; }
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
 
-        {
-            let mut buf = self.buffer.borrow_mut();
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.diagnostics.borrow().clone()
+    }
+}
 
-            buf[self.glimpse_origin.1][self.glimpse_origin.0] = self.c('+');
-            buf[self.glimpse_origin.1 + self.glimpse_size.1 - 1][self.glimpse_origin.0] = self.c('+');
-            buf[self.glimpse_origin.1 + self.glimpse_size.1 - 1][self.glimpse_origin.0 + self.glimpse_size.0 - 1] = self.c('+');
-            buf[self.glimpse_origin.1][self.glimpse_origin.0 + self.glimpse_size.0 - 1] = self.c('+');
+impl LayoutContext {
+    pub fn frame(&mut self, inner: impl FnOnce(LayoutContext)) {
+        if self.glimpse_size.0 < 2 || self.glimpse_size.1 < 2 {
+            self.warn("frame collapsed, glimpse is too small");
+            return;
+        }
 
-            for y in (self.glimpse_origin.1 + 1)..(self.glimpse_origin.1 + self.glimpse_size.1 - 1) {
-                buf[y][self.glimpse_origin.0] = self.c('|');
-                buf[y][self.glimpse_origin.0 + self.glimpse_size.0 - 1] = self.c('|');
-            }
+        self.border();
+        self.framed(inner);
+    }
 
-            for x in (self.glimpse_origin.0 + 1)..(self.glimpse_origin.0 + self.glimpse_size.0 - 1) {
-                buf[self.glimpse_origin.1][x] = self.c('-');
-                buf[self.glimpse_origin.1 + self.glimpse_size.1 - 1][x] = self.c('-');
-            }
+    pub fn frame_titled(&mut self, title: &str, align: Align, inner: impl FnOnce(LayoutContext)) {
+        if self.glimpse_size.0 < 2 || self.glimpse_size.1 < 2 {
+            self.warn("frame collapsed, glimpse is too small");
+            return;
         }
 
-        self.padding(1, 1, 1, 1, inner);
+        let title = self.markup(title, false).unwrap_or_default();
+
+        self.border();
+        self.title(&title, align);
+        self.framed(inner);
     }
 
-    pub fn vsplit(&mut self, split: isize, up: fn(LayoutContext), down: fn(LayoutContext)) {
+    pub fn vsplit(&mut self, split: isize, up: impl FnOnce(LayoutContext), down: impl FnOnce(LayoutContext)) {
         let split_loc = if split >= 0 {
             split
         } else {
-            self.glimpse_size.1 as isize - split.abs()
+            self.height() as isize - split.abs()
         };
 
         if split_loc < 0 {
-            down(self.clone());
+            self.warn(format!("split at {} is above the glimpse, only the lower part is drawn", split));
+            down(self.child("vsplit.down", self.glimpse_origin, self.glimpse_size));
         } else if split_loc as usize > self.glimpse_size.1 {
-            up(self.clone());
+            self.warn(format!("split at {} is below the glimpse, only the upper part is drawn", split));
+            up(self.child("vsplit.up", self.glimpse_origin, self.glimpse_size));
         } else {
             let split_loc = split_loc as usize;
 
-            for x in self.glimpse_origin.0..(self.glimpse_origin.0 + self.glimpse_size.0) {
-                self.buffer.borrow_mut()[self.glimpse_origin.1 + split_loc][x] = self.c('-');
-            }
+            self.hline(self.glimpse_origin.0, self.glimpse_origin.0 + self.glimpse_size.0, self.glimpse_origin.1 + split_loc);
 
             if split_loc > 0 {
-                let mut ctx = self.clone();
-                ctx.glimpse_size = (self.glimpse_size.0, split_loc);
-                up(ctx);
+                up(self.child("vsplit.up", self.glimpse_origin, (self.glimpse_size.0, split_loc)));
+            } else {
+                self.warn(format!("split at {} leaves no rows above the line, the upper part is not drawn", split));
             }
 
-            if split_loc < self.glimpse_size.1 - 1 {
-                let mut ctx = self.clone();
-                ctx.glimpse_origin = (self.glimpse_origin.0, self.glimpse_origin.1 + split_loc + 1);
-                ctx.glimpse_size = (self.glimpse_size.0, self.glimpse_size.1 - split_loc - 1);
-                down(ctx);
+            if split_loc + 1 < self.glimpse_size.1 {
+                down(self.child("vsplit.down", (self.glimpse_origin.0, self.glimpse_origin.1 + split_loc + 1), (self.glimpse_size.0, self.glimpse_size.1 - split_loc - 1)));
+            } else {
+                self.warn(format!("split at {} leaves no rows below the line, the lower part is not drawn", split));
             }
         }
     }
 
-    pub fn hsplit(&mut self, split: isize, left: fn(LayoutContext), right: fn(LayoutContext)) {
+    pub fn hsplit(&mut self, split: isize, left: impl FnOnce(LayoutContext), right: impl FnOnce(LayoutContext)) {
         let split_loc = if split >= 0 {
             split
         } else {
@@ -93,200 +232,1068 @@ impl LayoutContext {
         };
 
         if split_loc < 0 {
-            right(self.clone());
+            self.warn(format!("split at {} is left of the glimpse, only the right part is drawn", split));
+            right(self.child("hsplit.right", self.glimpse_origin, self.glimpse_size));
         } else if split_loc as usize > self.glimpse_size.0 {
-            left(self.clone());
+            self.warn(format!("split at {} is right of the glimpse, only the left part is drawn", split));
+            left(self.child("hsplit.left", self.glimpse_origin, self.glimpse_size));
         } else {
             let split_loc = split_loc as usize;
 
-            for y in self.glimpse_origin.1..(self.glimpse_origin.1 + self.glimpse_size.1) {
-                self.buffer.borrow_mut()[y][self.glimpse_origin.0 + split_loc] = self.c('|');
-            }
+            self.vline(self.glimpse_origin.0 + split_loc, self.glimpse_origin.1, self.glimpse_origin.1 + self.glimpse_size.1);
 
             if split_loc > 0 {
-                let mut ctx = self.clone();
-                ctx.glimpse_size = (split_loc, self.glimpse_size.1);
-                left(ctx);
+                left(self.child("hsplit.left", self.glimpse_origin, (split_loc, self.glimpse_size.1)));
+            } else {
+                self.warn(format!("split at {} leaves no columns left of the line, the left part is not drawn", split));
             }
 
-            if split_loc < self.glimpse_size.0 - 1 {
-                let mut ctx = self.clone();
-                ctx.glimpse_origin = (self.glimpse_origin.0 + split_loc + 1, self.glimpse_origin.1);
-                ctx.glimpse_size = (self.glimpse_size.0 - split_loc - 1, self.glimpse_size.1);
-                right(ctx);
+            if split_loc + 1 < self.glimpse_size.0 {
+                right(self.child("hsplit.right", (self.glimpse_origin.0 + split_loc + 1, self.glimpse_origin.1), (self.glimpse_size.0 - split_loc - 1, self.glimpse_size.1)));
+            } else {
+                self.warn(format!("split at {} leaves no columns right of the line, the right part is not drawn", split));
             }
         }
     }
 
-    pub fn padding(&mut self, left: usize, right: usize, up: usize, down: usize, inner: fn(LayoutContext)) {
-        if self.glimpse_size.0 < left + right + 1 { return; }
-        if self.glimpse_size.1 < up + down + 1 { return; }
+    pub fn measure(&self, width: usize, inner: impl FnOnce(LayoutContext)) -> (usize, usize) {
+        let extent = Rc::new(Cell::new((0, 0)));
 
+        // content is measured at its full height, the page height stays the reference for relative sizes
         let mut ctx = self.clone();
-        ctx.glimpse_origin = (self.glimpse_origin.0 + left, self.glimpse_origin.1 + up);
-        ctx.glimpse_size = (self.glimpse_size.0 - left - right, self.glimpse_size.1 - up - down);
+        ctx.glimpse_size = (width, FLOW_HEIGHT);
+        ctx.flow = None;
+        ctx.unbounded = Some(self.flow.map_or(self.height(), |flow| flow.height.min(self.height())));
+        ctx.measure = Some(Measure {
+            origin: self.glimpse_origin,
+            extent: extent.clone(),
+        });
         inner(ctx);
+
+        extent.get()
+    }
+
+    pub fn measure_text(&self, text: &str, width: usize) -> (usize, usize) {
+        let symbols = self.literal(text);
+        self.measure_wrapped(&symbols, width)
     }
 
-    pub fn text(&mut self, text: &str) {
-        let mut x = 0;
-        let mut y = 0;
+    pub fn measure_ftext(&self, text: &str, width: usize) -> (usize, usize) {
+        let symbols = markup::parse(text, &self.theme).unwrap_or_else(|_| self.literal(text));
+        self.measure_wrapped(&symbols, width)
+    }
+
+    pub fn vstack(&mut self, stack: &Stack, mut child: impl FnMut(usize, LayoutContext)) {
+        let lengths = stack.distribute(self.height());
+        let mut y = self.glimpse_origin.1;
 
-        for c in text.chars() {
-            match c {
-                '\n' => {
-                    x = 0;
-                    y += 1;
+        for (i, length) in lengths.iter().enumerate() {
+            if i > 0 {
+                if stack.has_separators() && y + stack.separator_offset() < self.glimpse_origin.1 + self.glimpse_size.1 {
+                    self.hline(self.glimpse_origin.0, self.glimpse_origin.0 + self.glimpse_size.0, y + stack.separator_offset());
                 }
-                c => {
-                    self.buffer.borrow_mut()[self.glimpse_origin.1 + y][self.glimpse_origin.0 + x] = self.c(c);
-                    x += 1;
+                y += stack.gap();
+            }
+
+            if *length > 0 {
+                child(i, self.child(&format!("vstack[{}]", i), (self.glimpse_origin.0, y), (self.glimpse_size.0, *length)));
+            } else {
+                self.warn(format!("stack child {} collapsed to zero height", i));
+            }
+            y += length;
+        }
+    }
+
+    pub fn hstack(&mut self, stack: &Stack, mut child: impl FnMut(usize, LayoutContext)) {
+        let lengths = stack.distribute(self.glimpse_size.0);
+        let mut x = self.glimpse_origin.0;
+
+        for (i, length) in lengths.iter().enumerate() {
+            if i > 0 {
+                if stack.has_separators() && x + stack.separator_offset() < self.glimpse_origin.0 + self.glimpse_size.0 {
+                    self.vline(x + stack.separator_offset(), self.glimpse_origin.1, self.glimpse_origin.1 + self.glimpse_size.1);
                 }
+                x += stack.gap();
+            }
+
+            if *length > 0 {
+                child(i, self.child(&format!("hstack[{}]", i), (x, self.glimpse_origin.1), (*length, self.glimpse_size.1)));
+            } else {
+                self.warn(format!("stack child {} collapsed to zero width", i));
+            }
+            x += length;
+        }
+    }
+
+    pub fn vsplit_fit(&mut self, fit: Fit, mut up: impl FnMut(LayoutContext), mut down: impl FnMut(LayoutContext)) {
+        let split = match fit {
+            Fit::First => self.measure(self.glimpse_size.0, &mut up).1 as isize,
+            Fit::Second => -(self.measure(self.glimpse_size.0, &mut down).1 as isize) - 1,
+        };
+
+        self.vsplit(split, up, down);
+    }
+
+    pub fn hsplit_fit(&mut self, fit: Fit, mut left: impl FnMut(LayoutContext), mut right: impl FnMut(LayoutContext)) {
+        let split = match fit {
+            Fit::First => self.measure(self.glimpse_size.0, &mut left).0 as isize,
+            Fit::Second => -(self.measure(self.glimpse_size.0, &mut right).0 as isize) - 1,
+        };
+
+        self.hsplit(split, left, right);
+    }
+
+    pub fn flow(&mut self, inner: impl FnOnce(LayoutContext)) {
+        let mut ctx = self.child("flow", self.glimpse_origin, self.glimpse_size);
+        if ctx.flow.is_none() {
+            ctx.flow = Some(Flow {
+                top: self.glimpse_origin.1,
+                height: self.glimpse_size.1,
+            });
+            ctx.unbounded = Some(self.glimpse_size.1);
+            ctx.glimpse_size.1 = FLOW_HEIGHT;
+        }
+        inner(ctx);
+    }
+
+    pub fn padding(&mut self, left: usize, right: usize, up: usize, down: usize, inner: impl FnOnce(LayoutContext)) {
+        if self.glimpse_size.0 < left + right + 1 || self.glimpse_size.1 < up + down + 1 {
+            self.warn(format!("padding ({}, {}, {}, {}) leaves no space in the glimpse", left, right, up, down));
+            return;
+        }
+
+        inner(self.child("padding", (self.glimpse_origin.0 + left, self.glimpse_origin.1 + up), (self.glimpse_size.0 - left - right, self.glimpse_size.1 - up - down)));
+    }
+
+    pub fn line_style(&mut self, style: LineStyle) {
+        // lines always join at least two directions, so the half-line glyphs are never drawn
+        let missing = self.missing_glyphs((1..16u8).filter(|mask| mask.count_ones() >= 2).map(|mask| style.glyph(mask)));
+        if missing.is_empty() {
+            self.line_style = style;
+        } else {
+            self.warn(format!("font has no glyphs for {:?} lines ({}), drawing ascii lines instead", style, missing));
+            self.line_style = LineStyle::Ascii;
+        }
+    }
+
+    pub fn fill(&mut self, color: (f32, f32, f32)) {
+        let visible = self.visible_rows();
+        let top = self.glimpse_origin.1.max(visible.start);
+        let bottom = (self.glimpse_origin.1 + self.glimpse_size.1).min(visible.end);
+
+        for y in top..bottom {
+            for x in self.glimpse_origin.0..(self.glimpse_origin.0 + self.glimpse_size.0) {
+                self.write(x, y, |cell, _| cell.background = Some(color));
             }
+        }
+    }
+
+    pub fn image_style(&mut self, style: ImageStyle) {
+        let missing = self.missing_glyphs(style.mode_chars());
+        if missing.is_empty() {
+            self.image_style = style;
+        } else {
+            let shown: String = missing.chars().take(8).collect();
+            let more = if missing.chars().count() > 8 { "…" } else { "" };
+            self.warn(format!("font has no glyphs for the image mode ({}{}), drawing ascii images instead", shown, more));
+            self.image_style = style.mode(Mode::Ascii);
+        }
+    }
+
+    pub fn hyphenate(&mut self, enabled: bool) {
+        self.hyphenate = enabled;
+    }
+
+    pub fn overflow(&mut self, policy: OverflowPolicy) {
+        self.overflow = policy;
+    }
+
+    pub fn text(&mut self, text: &str) -> Option<Overflow> {
+        self.text_aligned(text, Align::Left, VAlign::Top)
+    }
+
+    pub fn text_aligned(&mut self, text: &str, align: Align, valign: VAlign) -> Option<Overflow> {
+        let symbols = self.literal(text);
+        self.write_wrapped(&symbols, align, valign)
+    }
+
+    pub fn ftext(&mut self, text: &str) -> Option<Overflow> {
+        self.ftext_aligned(text, Align::Left, VAlign::Top)
+    }
+
+    pub fn ftext_aligned(&mut self, text: &str, align: Align, valign: VAlign) -> Option<Overflow> {
+        let symbols = self.markup(text, false).unwrap_or_default();
+        self.write_wrapped(&symbols, align, valign)
+    }
+
+    pub fn try_ftext(&mut self, text: &str) -> Result<Option<Overflow>, MarkupError> {
+        self.try_ftext_aligned(text, Align::Left, VAlign::Top)
+    }
+
+    pub fn try_ftext_aligned(&mut self, text: &str, align: Align, valign: VAlign) -> Result<Option<Overflow>, MarkupError> {
+        let symbols = self.markup(text, true)?;
+        Ok(self.write_wrapped(&symbols, align, valign))
+    }
+
+    pub fn list(&mut self, list: &List) -> Option<Overflow> {
+        self.draw_list(list, false).unwrap_or_default()
+    }
+
+    pub fn try_list(&mut self, list: &List) -> Result<Option<Overflow>, MarkupError> {
+        self.draw_list(list, true)
+    }
+
+    fn draw_list(&mut self, list: &List, strict: bool) -> Result<Option<Overflow>, MarkupError> {
+        let mut rows = Vec::new();
+        self.list_rows(list, 0, &mut rows, strict)?;
 
-            if x >= self.glimpse_size.0 {
-                x = 0;
-                y += 1;
+        let height = rows.len();
+        let width = self.glimpse_size.0;
+        let overflow = self.clip(&mut rows, |(indent, symbols)| ellipsize(symbols, width.saturating_sub(*indent)));
+
+        for (y, (indent, symbols)) in rows.iter().enumerate() {
+            for (x, symbol) in symbols.iter().enumerate() {
+                self.put(self.glimpse_origin.0 + indent + x, self.glimpse_origin.1 + y, *symbol);
             }
         }
 
-        if x == 0 && y > 0 { y -= 1; }
-        self.glimpse_origin.1 += y + 1;
-        self.glimpse_size.1 = (self.glimpse_size.1 as isize - y as isize - 1).max(0) as usize;
+        self.consume(height);
+
+        Ok(overflow)
+    }
+
+    pub fn table(&mut self, table: &Table) -> Option<Overflow> {
+        self.draw_table(table, false).unwrap_or_default()
+    }
+
+    pub fn try_table(&mut self, table: &Table) -> Result<Option<Overflow>, MarkupError> {
+        self.draw_table(table, true)
     }
 
-    pub fn ftext(&mut self, text: &str) {
-        let chars: Vec<char> = text.chars().collect();
+    fn draw_table(&mut self, table: &Table, strict: bool) -> Result<Option<Overflow>, MarkupError> {
+        let columns = table.columns();
+        let mut cells = Vec::new();
+        if let Some(header) = table.header_cells() {
+            cells.push(self.table_cells(table, header, true, strict)?);
+        }
+        for (i, row) in table.rows().iter().enumerate() {
+            if row.len() > columns {
+                self.warn(format!("table row {} has {} cells, only {} columns are drawn", i, row.len(), columns));
+            }
+            cells.push(self.table_cells(table, row, false, strict)?);
+        }
+
+        let natural: Vec<usize> = (0..columns)
+            .map(|c| cells.iter().map(|row| self.measure_wrapped(&row[c].0, usize::MAX).0).max().unwrap_or(0))
+            .collect();
+        let widths = table.widths(self.glimpse_size.0, &natural);
+        if widths.contains(&0) {
+            self.warn("table column collapsed to zero width");
+        }
+
+        let mut starts = Vec::with_capacity(columns);
+        let mut x = table.edge();
+        for width in &widths {
+            starts.push(x);
+            x += width + table.gap();
+        }
+        let table_width = (x + table.edge()).saturating_sub(table.gap());
 
-        let mut x = 0;
-        let mut y = 0;
-        let mut i = 0;
+        let mut rows = Vec::new();
+        if table.has_borders() { rows.push(TableRow::Rule(DOWN)); }
+        for (i, row) in cells.iter().enumerate() {
+            let wrapped: Vec<_> = row.iter().zip(&widths).map(|((symbols, _), width)| wrap(symbols, *width, self.hyphenate)).collect();
+            let height = wrapped.iter().map(|lines| lines.len()).max().unwrap_or(0).max(1);
 
-        let mut color = self.fg_color;
-        let mut bold = false;
-        let mut italic = false;
+            for y in 0..height {
+                rows.push(TableRow::Cells(wrapped.iter().zip(row).zip(&widths).map(|((lines, (_, align)), width)| {
+                    let Some(line) = lines.get(y) else { return (0, Vec::new()); };
+                    let symbols = match align {
+                        Align::Justify if !line.last => justify(&line.symbols, *width),
+                        _ => line.symbols.clone(),
+                    };
+                    let offset = match align {
+                        Align::Left | Align::Justify => 0,
+                        Align::Center => (width - symbols.len()) / 2,
+                        Align::Right => width - symbols.len(),
+                    };
+                    (offset, symbols)
+                }).collect()));
+            }
 
-        while i < chars.len() {
-            match chars[i] {
-                '\n' => {
-                    x = 0;
-                    y += 1;
+            if i == 0 && table.header_cells().is_some() && table.has_borders() {
+                rows.push(TableRow::Rule(UP | DOWN));
+            }
+        }
+        if table.has_borders() { rows.push(TableRow::Rule(UP)); }
+
+        let height = rows.len();
+        let overflow = self.clip(&mut rows, |row| {
+            if let TableRow::Cells(cells) = row {
+                for ((offset, symbols), width) in cells.iter_mut().zip(&widths) {
+                    ellipsize(symbols, width.saturating_sub(*offset));
                 }
-                '<' => {
-                    if i + 4 < chars.len() {
-                        match chars[i..i+4].iter().collect::<String>().as_str() {
-                            "<fg>" => {
-                                color = self.fg_color;
-                                bold = false;
-                                italic = false;
-                                i += 3;
-                            },
-                            "<h1>" => {
-                                color = self.h1_color;
-                                i += 3;
-                            },
-                            "<h2>" => {
-                                color = self.h2_color;
-                                i += 3;
-                            },
-                            "<h3>" => {
-                                color = self.h3_color;
-                                i += 3;
-                            },
-                            "<h4>" => {
-                                color = self.h4_color;
-                                i += 3;
-                            }
-                            "<bo>" => {
-                                bold = true;
-                                i += 3;
-                            }
-                            "<it>" => {
-                                italic = true;
-                                i += 3;
-                            }
-                            _ => {
-                                self.buffer.borrow_mut()[self.glimpse_origin.1 + y][self.glimpse_origin.0 + x] = Symbol {
-                                    character: '<',
-                                    color,
-                                    bold,
-                                    italic,
-                                };
-                                x += 1;
-                            }
-                        }
-                    } else {
-                        self.buffer.borrow_mut()[self.glimpse_origin.1 + y][self.glimpse_origin.0 + x] = Symbol {
-                            character: '<',
-                            color,
-                            bold,
-                            italic,
-                        };
+            }
+        });
+
+        let (x0, y0) = self.glimpse_origin;
+        let separators: Vec<usize> = starts.iter().skip(1).map(|x| x - 2).collect();
+        for (y, row) in rows.iter().enumerate() {
+            match row {
+                TableRow::Rule(mask) => {
+                    for x in 0..table_width {
+                        let mut m = LEFT | RIGHT;
+                        if x == 0 { m = RIGHT | mask; }
+                        if x + 1 == table_width { m = LEFT | mask; }
+                        if separators.contains(&x) { m |= mask; }
+                        self.line(x0 + x, y0 + y, m);
                     }
                 }
-                c => {
-                    self.buffer.borrow_mut()[self.glimpse_origin.1 + y][self.glimpse_origin.0 + x] = Symbol {
-                        character: c,
-                        color,
-                        bold,
-                        italic,
-                    };
-                    x += 1;
+                TableRow::Cells(cells) => {
+                    if table.has_borders() {
+                        for x in std::iter::once(0).chain(separators.iter().copied()).chain(std::iter::once(table_width - 1)) {
+                            self.line(x0 + x, y0 + y, UP | DOWN);
+                        }
+                    }
+                    for ((offset, symbols), start) in cells.iter().zip(&starts) {
+                        for (x, symbol) in symbols.iter().enumerate() {
+                            self.put(x0 + start + offset + x, y0 + y, *symbol);
+                        }
+                    }
                 }
             }
+        }
+
+        self.consume(height);
+
+        Ok(overflow)
+    }
+
+    pub fn meter(&mut self, meter: &Meter, label: &str, value: f32) -> Option<Overflow> {
+        self.meters(meter, &[(label, value)])
+    }
+
+    pub fn meters(&mut self, meter: &Meter, rows: &[(&str, f32)]) -> Option<Overflow> {
+        self.draw_meters(meter, rows, false).unwrap_or_default()
+    }
+
+    pub fn try_meters(&mut self, meter: &Meter, rows: &[(&str, f32)]) -> Result<Option<Overflow>, MarkupError> {
+        self.draw_meters(meter, rows, true)
+    }
+
+    fn draw_meters(&mut self, meter: &Meter, rows: &[(&str, f32)], strict: bool) -> Result<Option<Overflow>, MarkupError> {
+        let missing = self.missing_glyphs(meter.chars());
+        let fallback;
+        let meter = if missing.is_empty() {
+            meter
+        } else {
+            self.warn(format!("font has no glyphs for {:?} meters ({}), drawing ascii bars instead", meter.glyphs(), missing));
+            fallback = meter.ascii();
+            &fallback
+        };
+
+        let labels = rows.iter().map(|(label, _)| self.markup(label, strict)).collect::<Result<Vec<_>, _>>()?;
+        let label_width = meter.fixed_label_width().unwrap_or_else(|| labels.iter().map(|l| l.len()).max().unwrap_or(0));
+
+        let filled = self.color(meter.filled_color());
+        let empty = self.color(meter.empty_color());
+
+        let brackets = if meter.has_brackets() { 2 } else { 0 };
+        let percent = if meter.has_percent() { 5 } else { 0 };
+        let width = self.glimpse_size.0;
+        let bar_width = width.saturating_sub(label_width + 1 + brackets + percent);
+        if bar_width == 0 {
+            self.warn("meter has no room for its bar");
+        }
+
+        let mut lines: Vec<Vec<Symbol>> = labels.into_iter().zip(rows).map(|(mut label, (_, value))| {
+            let value = if value.is_finite() {
+                *value
+            } else {
+                self.warn(format!("meter value {} is not finite, drawing it as 0", value));
+                0.0
+            };
+            if !(0.0..=1.0).contains(&value) {
+                self.warn(format!("meter value {} clamped to 0..1", value));
+            }
+            let value = value.clamp(0.0, 1.0);
 
-            if x >= self.glimpse_size.0 {
-                x = 0;
-                y += 1;
+            label.truncate(label_width);
+            label.resize(label_width + 1, self.c(' '));
+            if meter.has_brackets() { label.push(self.c('[')); }
+            label.extend(meter.bar(value, bar_width).into_iter().map(|(c, full)| Symbol {
+                color: if full { filled } else { empty },
+                ..self.c(c)
+            }));
+            if meter.has_brackets() { label.push(self.c(']')); }
+            if meter.has_percent() {
+                label.extend(format!(" {:>3}%", (value * 100.0).round()).chars().map(|c| self.c(c)));
             }
+            label
+        }).collect();
 
-            i += 1;
+        let height = lines.len();
+        let overflow = self.clip(&mut lines, |line| ellipsize(line, width));
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, symbol) in line.iter().enumerate() {
+                self.put(self.glimpse_origin.0 + x, self.glimpse_origin.1 + y, *symbol);
+            }
         }
 
-        if x == 0 && y > 0 { y -= 1; }
-        self.glimpse_origin.1 += y + 1;
-        self.glimpse_size.1 = (self.glimpse_size.1 as isize - y as isize - 1).max(0) as usize;
+        self.consume(height);
+
+        Ok(overflow)
     }
 
-    pub fn img(&mut self, img: &Vec<f32>, w: usize, h: usize) {
-        for y in 0..h {
-            for x in 0..w {
-                if x < self.glimpse_size.0 && y < self.glimpse_size.1 {
-                    let pix = (y * w + x) * 3;
+    pub fn timeline(&mut self, timeline: &Timeline) -> Option<Overflow> {
+        let (x0, y0) = self.glimpse_origin;
+        let width = self.glimpse_size.0;
+        if width == 0 {
+            self.warn("timeline has no room for its axis");
+            return None;
+        }
 
-                    let mut ch = ' ';
-                    let brightness = 0.299 * img[pix + 0] + 0.587 * img[pix + 1] + 0.114 * img[pix + 2];
-                    if brightness < 0.2 {
-                        ch = '.';
-                    } else if 0.2 <= brightness && brightness < 0.4 {
-                        ch = ':';
-                    } else if 0.4 <= brightness && brightness < 0.6 {
-                        ch = 'o';
-                    } else if 0.6 <= brightness && brightness < 0.8 {
-                        ch = '0';
-                    } else if 0.8 <= brightness  {
-                        ch = '@';
-                    }
+        let (from, to) = timeline.years();
+        let cells_per_year = width as f32 / (to - from) as f32;
+        let column = |years: f32| (((years - from as f32) * cells_per_year).round().max(0.0) as usize).min(width);
 
-                    self.buffer.borrow_mut()[self.glimpse_origin.1 + y][self.glimpse_origin.0 + x] = Symbol {
-                        character: ch,
-                        color: (img[pix + 0], img[pix + 1], img[pix + 2]),
-                        bold: false,
-                        italic: false,
-                    };
+        // greedy lanes, an entry goes on the first row where it does not overlap the bar or label before it
+        let mut lanes: Vec<usize> = Vec::new();
+        let mut bars = Vec::new();
+        let mut entries: Vec<_> = timeline.entries().iter().collect();
+        entries.sort_by_key(|entry| entry.start);
+
+        for entry in entries {
+            let start = column(entry.start.years()).min(width - 1);
+            let end = column(entry.end.years()).max(start + 1);
+            let label_len = entry.label.chars().count();
+            let extent = if label_len > end - start { end + 1 + label_len } else { end } + 1;
+
+            let lane = match lanes.iter().position(|lane_end| *lane_end <= start) {
+                Some(lane) => lane,
+                None => {
+                    lanes.push(0);
+                    lanes.len() - 1
+                }
+            };
+            lanes[lane] = extent;
+            bars.push((lane, start, end, entry));
+        }
+
+        let height = lanes.len() + 2;
+        let mut rows: Vec<usize> = (0..height).collect();
+        let overflow = self.clip(&mut rows, |_| {});
+        let shown = rows.len();
+
+        for (lane, start, end, entry) in bars.into_iter().filter(|(lane, ..)| *lane < shown) {
+            let color = self.color(&entry.color);
+            let y = y0 + lane;
+            let label: Vec<char> = entry.label.chars().collect();
+            let inside = label.len() <= end - start;
+
+            for x in start..end {
+                let character = if inside { label.get(x - start).copied().unwrap_or(' ') } else { ' ' };
+                self.put(x0 + x, y, Symbol { color: self.theme.bg, background: Some(color), ..self.c(character) });
+            }
+            if !inside {
+                for (i, character) in label.iter().enumerate() {
+                    self.put(x0 + end + 1 + i, y, Symbol { color, ..self.c(*character) });
+                }
+            }
+        }
+
+        let axis = y0 + lanes.len();
+        let step = timeline.label_step(cells_per_year);
+        if lanes.len() < shown {
+            for x in 0..width {
+                self.line(x0 + x, axis, LEFT | RIGHT);
+            }
+        }
+        for year in from..=to {
+            let x = column(year as f32);
+            let labelled = (year - from) % step == 0;
+            if lanes.len() < shown && x < width && (labelled || cells_per_year >= 2.0) {
+                self.line(x0 + x, axis, LEFT | RIGHT | DOWN);
+            }
+            if lanes.len() + 1 < shown && labelled && x + year.to_string().len() <= width {
+                for (i, character) in year.to_string().chars().enumerate() {
+                    self.put(x0 + x + i, axis + 1, self.c(character));
                 }
             }
         }
+
+        self.consume(height);
+        overflow
+    }
+
+    pub fn image_file(&mut self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let image = image::open(path)?;
+        self.image(&image);
+        Ok(())
+    }
+
+    pub fn image(&mut self, image: &DynamicImage) {
+        if image.width() == 0 || image.height() == 0 || self.glimpse_size.0 == 0 {
+            self.warn("image is empty or the glimpse has no width");
+            return;
+        }
+
+        // a cell covers aspect times more height than width, so squash the rows to keep proportions
+        let ratio = image.height() as f32 / (image.width() as f32 * self.aspect);
+        let mut w = self.glimpse_size.0;
+        let mut h = ((w as f32 * ratio).round() as usize).max(1);
+        let bounded = self.bounded();
+        if bounded && h > self.glimpse_size.1 {
+            h = self.glimpse_size.1.max(1);
+            w = ((h as f32 / ratio).round() as usize).clamp(1, self.glimpse_size.0);
+        }
+
+        let (sx, sy) = self.image_style.cell_pixels();
+        let pixels = image.resize_exact((w * sx) as u32, (h * sy) as u32, FilterType::Triangle).to_rgb32f().into_raw();
+        self.img(&pixels, w * sx, h * sy);
+        self.consume(h);
+    }
+
+    pub fn img(&mut self, img: &[f32], w: usize, h: usize) {
+        let (mut symbols, w, h) = self.image_style.symbols(img, w, h);
+        let tint = self.image_style.tint().map_or(self.theme.fg, |name| self.color(name));
+        for symbol in &mut symbols {
+            symbol.color = self.image_style.recolor(symbol.color, &self.theme, tint);
+            symbol.background = symbol.background.map(|bg| self.image_style.recolor(bg, &self.theme, tint));
+        }
+        if w > self.glimpse_size.0 || h > self.glimpse_size.1 {
+            self.warn(format!("image of {}x{} clipped to the glimpse", w, h));
+        }
+
+        for y in 0..h.min(self.glimpse_size.1) {
+            for x in 0..w.min(self.glimpse_size.0) {
+                self.put(self.glimpse_origin.0 + x, self.glimpse_origin.1 + y, symbols[y * w + x]);
+            }
+        }
     }
 }
 
 impl LayoutContext {
+    fn child(&self, name: &str, origin: (usize, usize), size: (usize, usize)) -> LayoutContext {
+        let mut ctx = self.clone();
+        ctx.glimpse_origin = origin;
+        ctx.glimpse_size = size;
+        ctx.unbounded = self.unbounded.filter(|_| size.1 > FLOW_HEIGHT / 2);
+
+        if self.measure.is_none() {
+            let name = self.regions.borrow_mut().name(self.region, name);
+            ctx.path = Rc::from(format!("{}/{}", self.path, name));
+            ctx.region = self.regions.borrow_mut().register(Region {
+                parent: Some(self.region),
+                path: ctx.path.clone(),
+                visible: ctx.visible_bounds(),
+            });
+        }
+
+        ctx
+    }
+
+    // flow contexts have no bottom, proportional sizes and offsets from the bottom use one page instead
+    fn height(&self) -> usize {
+        self.unbounded.unwrap_or(self.glimpse_size.1)
+    }
+
+    fn bounded(&self) -> bool {
+        self.unbounded.is_none()
+    }
+
+    fn warn(&self, message: impl Into<String>) {
+        self.report(Severity::Warning, message.into());
+    }
+
+    fn error(&self, message: impl Into<String>) {
+        self.report(Severity::Error, message.into());
+    }
+
+    fn report(&self, severity: Severity, message: String) {
+        // the layout runs once per page, content outside a flow repeats on every page so it reports on the first
+        if self.measure.is_some() || (self.flow.is_none() && self.page > 0) { return; }
+
+        let (page, origin, size) = self.page_bounds();
+        self.diagnostics.borrow_mut().push(Diagnostic {
+            severity,
+            path: self.path.to_string(),
+            page,
+            origin,
+            size,
+            message,
+        });
+    }
+
+    // the page a glimpse starts on and its bounds on that page, flow glimpses are cut at the page end
+    fn page_bounds(&self) -> (usize, (usize, usize), (usize, usize)) {
+        let (x, y) = self.glimpse_origin;
+        let (w, h) = self.glimpse_size;
+        match self.flow {
+            Some(flow) if flow.height > 0 => {
+                let row = y.saturating_sub(flow.top);
+                let top = flow.top + row % flow.height;
+                (row / flow.height, (x, top), (w, h.min(flow.top + flow.height - top)))
+            }
+            _ => (self.page, (x, y), (w, h)),
+        }
+    }
+
+    // a measured frame has no bottom to draw, it closes one row below its content instead
+    fn framed(&self, inner: impl FnOnce(LayoutContext)) {
+        let (x0, y0) = self.glimpse_origin;
+        let mut child = self.child("frame", (x0 + 1, y0 + 1), (self.glimpse_size.0 - 2, self.glimpse_size.1 - 2));
+        if self.measure.is_none() || self.bounded() {
+            inner(child);
+            return;
+        }
+
+        let extent = Rc::new(Cell::new((0, 0)));
+        child.measure = Some(Measure {
+            origin: child.glimpse_origin,
+            extent: extent.clone(),
+        });
+        inner(child);
+        self.locate(x0 + self.glimpse_size.0 - 1, y0 + 1 + extent.get().1);
+    }
+
+    fn border(&self) {
+        let (x0, y0) = self.glimpse_origin;
+        let (x1, y1) = (x0 + self.glimpse_size.0 - 1, y0 + self.glimpse_size.1 - 1);
+
+        self.line(x0, y0, DOWN | RIGHT);
+        self.line(x1, y0, DOWN | LEFT);
+        self.line(x0, y1, UP | RIGHT);
+        self.line(x1, y1, UP | LEFT);
+
+        let visible = self.visible_rows();
+        for y in (y0 + 1).max(visible.start)..y1.min(visible.end) {
+            self.line(x0, y, UP | DOWN);
+            self.line(x1, y, UP | DOWN);
+        }
+
+        for x in (x0 + 1)..x1 {
+            self.line(x, y0, LEFT | RIGHT);
+            self.line(x, y1, LEFT | RIGHT);
+        }
+    }
+
+    fn title(&self, title: &[Symbol], align: Align) {
+        let space = self.glimpse_size.0.saturating_sub(8);
+        if space == 0 {
+            self.warn("frame is too narrow to show its title");
+            return;
+        }
+
+        if title.len() > space {
+            self.warn(format!("title truncated to {} of {} characters", space, title.len()));
+        }
+
+        let title = &title[..title.len().min(space)];
+        let offset = match align {
+            Align::Left | Align::Justify => 0,
+            Align::Center => (space - title.len()) / 2,
+            Align::Right => space - title.len(),
+        };
+
+        let x = self.glimpse_origin.0 + 2 + offset;
+        let y = self.glimpse_origin.1;
+
+        self.put(x, y, self.c('['));
+        self.put(x + 1, y, self.c(' '));
+        for (i, symbol) in title.iter().enumerate() {
+            self.put(x + 2 + i, y, *symbol);
+        }
+        self.put(x + 2 + title.len(), y, self.c(' '));
+        self.put(x + 3 + title.len(), y, self.c(']'));
+    }
+
+    fn hline(&self, x0: usize, x1: usize, y: usize) {
+        for x in x0..x1 {
+            self.line(x, y, LEFT | RIGHT);
+        }
+
+        if x0 > 0 { self.connect(x0 - 1, y, RIGHT); }
+        if x1 < self.view_size.0 { self.connect(x1, y, LEFT); }
+    }
+
+    fn vline(&self, x: usize, y0: usize, y1: usize) {
+        let visible = self.visible_rows();
+        for y in y0.max(visible.start)..y1.min(visible.end) {
+            self.line(x, y, UP | DOWN);
+        }
+
+        if y0 > visible.start { self.connect(x, y0 - 1, DOWN); }
+        if y1 < visible.end { self.connect(x, y1, UP); }
+    }
+
+    fn line(&self, x: usize, y: usize, mask: u8) {
+        if !self.contains(x, y) || !self.visible_rows().contains(&y) { return; }
+
+        let style = self.line_style;
+        let color = self.theme.fg;
+        self.write(x, y, |cell, lines| {
+            *lines |= mask;
+            *cell = Symbol {
+                character: style.glyph(*lines),
+                color,
+                bold: false,
+                italic: false,
+                background: cell.background,
+            };
+        });
+    }
+
+    fn connect(&self, x: usize, y: usize, mask: u8) {
+        if self.measure.is_some() { return; }
+
+        let style = self.line_style;
+        // only cells drawn by line() join, text that happens to look like a line stays as it is
+        self.update(x, y, |cell, lines| {
+            if *lines != 0 {
+                *lines |= mask;
+                cell.character = style.glyph(*lines);
+            }
+        });
+    }
+
+    fn visible_bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let visible = self.visible_rows();
+        let top = self.glimpse_origin.1.max(visible.start);
+        let bottom = self.glimpse_origin.1.saturating_add(self.glimpse_size.1).min(visible.end);
+        if top >= bottom || self.glimpse_size.0 == 0 { return None; }
+
+        let offset = self.flow.map_or(0, |flow| self.page * flow.height);
+        Some(((self.glimpse_origin.0, top - offset), (self.glimpse_size.0, bottom - top)))
+    }
+
+    fn visible_rows(&self) -> Range<usize> {
+        // lines and fills in an unbounded measured glimpse only mark its top row, the content sets the height
+        if self.measure.is_some() {
+            return if self.bounded() { 0..usize::MAX } else { 0..self.glimpse_origin.1 + 1 };
+        }
+
+        match self.flow {
+            Some(flow) => {
+                let top = flow.top + self.page * flow.height;
+                top..(top + flow.height)
+            }
+            None => 0..self.view_size.1,
+        }
+    }
+
+    fn contains(&self, x: usize, y: usize) -> bool {
+        let (x0, y0) = self.glimpse_origin;
+        let (w, h) = self.glimpse_size;
+        (x0..x0 + w).contains(&x) && (self.measure.is_some() || (y0..y0 + h).contains(&y))
+    }
+
+    fn put(&self, x: usize, y: usize, symbol: Symbol) {
+        if !self.contains(x, y) { return; }
+
+        self.write(x, y, |cell, lines| {
+            *cell = Symbol {
+                background: symbol.background.or(cell.background),
+                ..symbol
+            };
+            *lines = 0;
+        });
+    }
+
+    fn update(&self, x: usize, y: usize, f: impl FnOnce(&mut Symbol, &mut u8)) {
+        if let Some((x, y)) = self.locate(x, y) {
+            f(&mut self.buffer.borrow_mut()[y][x], &mut self.lines.borrow_mut()[y][x]);
+        }
+    }
+
+    fn write(&self, x: usize, y: usize, f: impl FnOnce(&mut Symbol, &mut u8)) {
+        let Some((bx, by)) = self.locate(x, y) else { return; };
+
+        if let Some(ownership) = &self.ownership {
+            let previous = ownership.borrow_mut().claim(bx, by, self.region, &self.regions.borrow());
+            if let Some(previous) = previous {
+                let path = self.regions.borrow().get(previous).path.clone();
+                self.warn(format!("overwrites cell ({}, {}) drawn by {}", bx, by, path));
+            }
+        }
+
+        f(&mut self.buffer.borrow_mut()[by][bx], &mut self.lines.borrow_mut()[by][bx]);
+    }
+
+    fn locate(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        if let Some(measure) = &self.measure {
+            if x >= measure.origin.0 && y >= measure.origin.1 {
+                let (w, h) = measure.extent.get();
+                measure.extent.set((w.max(x - measure.origin.0 + 1), h.max(y - measure.origin.1 + 1)));
+            }
+            return None;
+        }
+
+        let y = match self.flow {
+            Some(flow) if flow.height > 0 => {
+                let row = y.checked_sub(flow.top).filter(|row| *row < FLOW_HEIGHT / 2)?;
+                let page = row / flow.height;
+                if page >= MAX_FLOW_PAGES {
+                    self.error(format!("content runs past the limit of {} flow pages", MAX_FLOW_PAGES));
+                    return None;
+                }
+                self.pages.set(self.pages.get().max(page + 1));
+                if page != self.page { return None; }
+                flow.top + row % flow.height
+            }
+            Some(_) => return None,
+            None => y,
+        };
+
+        (x < self.view_size.0 && y < self.view_size.1).then_some((x, y))
+    }
+
+    fn measure_wrapped(&self, symbols: &[Symbol], width: usize) -> (usize, usize) {
+        let lines = wrap(symbols, width, self.hyphenate);
+        let w = lines.iter().map(|line| line.symbols.len()).max().unwrap_or(0);
+        (w, lines.len().max(1))
+    }
+
+    fn write_wrapped(&mut self, symbols: &[Symbol], align: Align, valign: VAlign) -> Option<Overflow> {
+        let width = self.glimpse_size.0;
+        let mut lines = wrap(symbols, width, self.hyphenate);
+
+        let bounded = self.bounded();
+        let height = lines.len();
+        let space = if bounded { self.glimpse_size.1.saturating_sub(height) } else { 0 };
+        let top = match valign {
+            VAlign::Top => 0,
+            VAlign::Middle => space / 2,
+            VAlign::Bottom => space,
+        };
+
+        let overflow = self.clip(&mut lines, |line| {
+            ellipsize(&mut line.symbols, width);
+            line.last = true;
+        });
+
+        for (y, line) in lines.iter().enumerate() {
+            let symbols = match align {
+                Align::Justify if !line.last => justify(&line.symbols, width),
+                _ => line.symbols.clone(),
+            };
+            let left = match align {
+                Align::Left | Align::Justify => 0,
+                Align::Center => (width - symbols.len()) / 2,
+                Align::Right => width - symbols.len(),
+            };
+
+            for (x, symbol) in symbols.iter().enumerate() {
+                self.put(self.glimpse_origin.0 + left + x, self.glimpse_origin.1 + top + y, *symbol);
+            }
+        }
+
+        self.consume(top + height);
+
+        overflow
+    }
+
+    fn clip<T>(&self, lines: &mut Vec<T>, ellipsis: impl FnOnce(&mut T)) -> Option<Overflow> {
+        let bounded = self.bounded();
+        if !bounded || lines.len() <= self.glimpse_size.1 {
+            return None;
+        }
+
+        let overflow = Overflow { hidden_lines: lines.len() - self.glimpse_size.1 };
+        match self.overflow {
+            OverflowPolicy::Error => self.error(format!("text does not fit, {} lines would be hidden", overflow.hidden_lines)),
+            _ => self.warn(format!("text truncated, {} lines hidden", overflow.hidden_lines)),
+        }
+
+        match self.overflow {
+            OverflowPolicy::Clip => lines.truncate(self.glimpse_size.1),
+            OverflowPolicy::Ellipsis => {
+                lines.truncate(self.glimpse_size.1);
+                if let Some(line) = lines.last_mut() {
+                    ellipsis(line);
+                }
+            }
+            OverflowPolicy::Error => lines.clear(),
+        }
+
+        Some(overflow)
+    }
+
+    fn list_rows(&self, list: &List, indent: usize, rows: &mut Vec<(usize, Vec<Symbol>)>, strict: bool) -> Result<(), MarkupError> {
+        let hang = list.hang();
+        let width = self.glimpse_size.0.saturating_sub(indent + hang);
+        let mut index = 0;
+
+        for entry in list.entries() {
+            match entry {
+                Entry::Item(text) => {
+                    let symbols = self.markup(text, strict)?;
+                    let marker: Vec<Symbol> = list.marker(index).chars().map(|c| self.c(c)).collect();
+                    index += 1;
+
+                    let mut first: Vec<Symbol> = if list.right_aligned() {
+                        std::iter::repeat_n(self.c(' '), list.marker_width() - marker.len()).chain(marker).collect()
+                    } else {
+                        marker
+                    };
+                    first.resize(hang, self.c(' '));
+
+                    let mut lines = wrap(&symbols, width, self.hyphenate).into_iter();
+                    if let Some(line) = lines.next() {
+                        first.extend(line.symbols);
+                    }
+                    rows.push((indent, first));
+                    rows.extend(lines.map(|line| (indent + hang, line.symbols)));
+                }
+                Entry::List(nested) => self.list_rows(nested, indent + hang, rows, strict)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn table_cells(&self, table: &Table, cells: &[TableCell], header: bool, strict: bool) -> Result<Vec<(Vec<Symbol>, Align)>, MarkupError> {
+        (0..table.columns()).map(|c| {
+            let Some(cell) = cells.get(c) else { return Ok((Vec::new(), table.column_align(c))); };
+            let text = if header { table.header_markup(&cell.text) } else { cell.text.clone() };
+            let symbols = match markup::parse(&text, &self.theme) {
+                Ok(symbols) => symbols,
+                Err(e) if strict => return Err(e),
+                // report against the cell as written, without the header style around it
+                Err(_) => self.markup(&cell.text, false)?,
+            };
+            Ok((symbols, cell.align.unwrap_or(table.column_align(c))))
+        }).collect()
+    }
+
+    fn consume(&mut self, rows: usize) {
+        let consumed = rows.max(1).min(self.glimpse_size.1);
+        self.glimpse_origin.1 += consumed;
+        self.glimpse_size.1 -= consumed;
+    }
+
+    fn color(&self, name: &str) -> (f32, f32, f32) {
+        self.theme.resolve(name).unwrap_or_else(|| {
+            self.warn(format!("unknown color '{}', using the foreground color", name));
+            self.theme.fg
+        })
+    }
+
+    // outside the try_ variants a markup error is reported and the text is drawn as written
+    fn markup(&self, text: &str, strict: bool) -> Result<Vec<Symbol>, MarkupError> {
+        match markup::parse(text, &self.theme) {
+            Err(e) if !strict => {
+                self.error(e.to_string());
+                Ok(self.literal(text))
+            }
+            result => result,
+        }
+    }
+
+    fn missing_glyphs(&self, chars: impl IntoIterator<Item = char>) -> String {
+        let Some(glyphs) = &self.glyphs else { return String::new(); };
+
+        let mut missing: Vec<char> = chars.into_iter().filter(|c| !glyphs.contains(c)).collect();
+        missing.sort_unstable();
+        missing.dedup();
+        missing.into_iter().collect()
+    }
+
+    fn literal(&self, text: &str) -> Vec<Symbol> {
+        text.chars().map(|c| self.c(c)).collect()
+    }
+
     fn c(&self, c: char) -> Symbol {
         Symbol {
             character: c,
-            color: self.fg_color,
+            color: self.theme.fg,
             bold: false,
             italic: false,
+            background: None,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(width: usize, height: usize) -> (LayoutContext, Rc<RefCell<Vec<Vec<Symbol>>>>) {
+        let theme = Theme {
+            bg: (0.0, 0.0, 0.0),
+            fg: (1.0, 1.0, 1.0),
+            h1: (1.0, 0.0, 0.0),
+            h2: (0.0, 1.0, 0.0),
+            h3: (0.0, 0.0, 1.0),
+            h4: (1.0, 1.0, 0.0),
+            palette: Default::default(),
+        };
+        let blank = Symbol { character: ' ', color: theme.fg, bold: false, italic: false, background: None };
+        let buffer = Rc::new(RefCell::new(vec![vec![blank; width]; height]));
+        let ctx = LayoutContext::new(buffer.clone(), (width, height), (0, 0), (width, height), Rc::new(theme), 0, Rc::new(Cell::new(1)));
+        (ctx, buffer)
+    }
+
+    fn rows(buffer: &Rc<RefCell<Vec<Vec<Symbol>>>>) -> Vec<String> {
+        buffer.borrow().iter().map(|row| row.iter().map(|s| s.character).collect()).collect()
+    }
+
+    #[test]
+    fn measures_text_past_the_glimpse() {
+        let (ctx, _) = context(10, 3);
+        let size = ctx.measure(4, |mut c| {
+            c.text("aa bb");
+            c.text("cc dd");
+            c.text("ee ff");
+        });
+        assert_eq!(size, (2, 6));
+    }
+
+    #[test]
+    fn measures_a_frame_around_its_content() {
+        let (ctx, _) = context(20, 4);
+        assert_eq!(ctx.measure(10, |mut c| c.frame(|mut c| { c.text("hi"); })), (10, 3));
+        assert_eq!(ctx.measure(10, |mut c| c.frame(|mut c| { c.text("a\nb\nc\nd\ne"); })), (10, 7));
+    }
+
+    #[test]
+    fn measures_an_hsplit_by_its_taller_side() {
+        let (ctx, _) = context(20, 4);
+        let size = ctx.measure(10, |mut c| c.hsplit(5, |mut l| { l.text("ab"); }, |mut r| { r.text("cd\nef"); }));
+        assert_eq!(size, (8, 2));
+    }
+
+    #[test]
+    fn measures_a_fill_by_its_content() {
+        let (ctx, _) = context(20, 4);
+        let size = ctx.measure(10, |mut c| {
+            c.fill((1.0, 0.0, 0.0));
+            c.text("x");
+        });
+        assert_eq!(size, (10, 1));
+    }
+
+    #[test]
+    fn fits_a_split_to_a_measured_hsplit() {
+        let (mut ctx, buffer) = context(12, 5);
+        ctx.vsplit_fit(Fit::First, |mut c| c.hsplit(5, |mut l| { l.text("a"); }, |mut r| { r.text("b\nc"); }), |mut c| { c.text("body"); });
+        assert_eq!(rows(&buffer), ["a    |b     ", "     |c     ", "-----+------", "body        ", "            "]);
+    }
+
+    #[test]
+    fn fits_a_split_to_a_measured_frame() {
+        let (mut ctx, buffer) = context(8, 5);
+        ctx.vsplit_fit(Fit::First, |mut c| c.frame(|mut c| { c.text("hi"); }), |mut c| { c.text("body"); });
+        assert_eq!(rows(&buffer), ["+------+", "|hi    |", "+------+", "--------", "body    "]);
+    }
+
+    #[test]
+    fn line_style_does_not_need_half_line_glyphs() {
+        let (mut ctx, buffer) = context(4, 3);
+        ctx.font_glyphs(Rc::new("─│┌┐└┘├┤┬┴┼".chars().collect()));
+        ctx.line_style(LineStyle::Single);
+        ctx.frame(|_| {});
+        assert!(ctx.diagnostics().is_empty());
+        assert_eq!(rows(&buffer), ["┌──┐", "│  │", "└──┘"]);
+    }
+}
@@ -0,0 +1,13 @@
+pub mod diagnostics;
+pub mod image_style;
+pub mod layout;
+pub mod lines;
+pub mod list;
+pub mod markup;
+pub mod meter;
+pub mod page;
+mod regions;
+pub mod stack;
+pub mod table;
+mod text;
+pub mod timeline;
@@ -0,0 +1,37 @@
+pub(crate) const UP: u8 = 1;
+pub(crate) const DOWN: u8 = 2;
+pub(crate) const LEFT: u8 = 4;
+pub(crate) const RIGHT: u8 = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineStyle {
+    #[default]
+    Ascii,
+    Single,
+    Double,
+    Heavy,
+    Rounded,
+}
+
+const SINGLE: [char; 16] = [' ', '╵', '╷', '│', '╴', '┘', '┐', '┤', '╶', '└', '┌', '├', '─', '┴', '┬', '┼'];
+const DOUBLE: [char; 16] = [' ', '║', '║', '║', '═', '╝', '╗', '╣', '═', '╚', '╔', '╠', '═', '╩', '╦', '╬'];
+const HEAVY: [char; 16] = [' ', '╹', '╻', '┃', '╸', '┛', '┓', '┫', '╺', '┗', '┏', '┣', '━', '┻', '┳', '╋'];
+const ROUNDED: [char; 16] = [' ', '╵', '╷', '│', '╴', '╯', '╮', '┤', '╶', '╰', '╭', '├', '─', '┴', '┬', '┼'];
+
+impl LineStyle {
+    pub fn glyph(self, mask: u8) -> char {
+        let mask = (mask & 0xf) as usize;
+        match self {
+            LineStyle::Ascii => match mask {
+                0 => ' ',
+                1..=3 => '|',
+                4 | 8 | 12 => '-',
+                _ => '+',
+            },
+            LineStyle::Single => SINGLE[mask],
+            LineStyle::Double => DOUBLE[mask],
+            LineStyle::Heavy => HEAVY[mask],
+            LineStyle::Rounded => ROUNDED[mask],
+        }
+    }
+}
@@ -0,0 +1,123 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Numbering {
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    Bullet(char),
+    Numbered(Numbering, char),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Entry {
+    Item(String),
+    List(List),
+}
+
+#[derive(Clone, Debug)]
+pub struct List {
+    marker: Marker,
+    start: usize,
+    gap: usize,
+    entries: Vec<Entry>,
+}
+
+impl List {
+    pub fn new(marker: Marker) -> Self {
+        Self {
+            marker,
+            start: 1,
+            gap: 1,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn start(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn item(mut self, text: &str) -> Self {
+        self.entries.push(Entry::Item(text.to_string()));
+        self
+    }
+
+    pub fn nested(mut self, list: List) -> Self {
+        self.entries.push(Entry::List(list));
+        self
+    }
+
+    pub(crate) fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub(crate) fn marker(&self, index: usize) -> String {
+        let n = self.start + index;
+        match self.marker {
+            Marker::Bullet(c) => c.to_string(),
+            Marker::Numbered(numbering, suffix) => {
+                let number = match numbering {
+                    Numbering::Decimal => n.to_string(),
+                    Numbering::LowerAlpha => alpha(n),
+                    Numbering::UpperAlpha => alpha(n).to_uppercase(),
+                    Numbering::LowerRoman => roman(n),
+                    Numbering::UpperRoman => roman(n).to_uppercase(),
+                };
+                format!("{}{}", number, suffix)
+            }
+        }
+    }
+
+    pub(crate) fn marker_width(&self) -> usize {
+        let items = self.entries.iter().filter(|e| matches!(e, Entry::Item(_))).count();
+        (0..items).map(|i| self.marker(i).chars().count()).max().unwrap_or(0)
+    }
+
+    pub(crate) fn hang(&self) -> usize {
+        self.marker_width() + self.gap
+    }
+
+    pub(crate) fn right_aligned(&self) -> bool {
+        matches!(self.marker, Marker::Numbered(..))
+    }
+}
+
+fn alpha(mut n: usize) -> String {
+    if n == 0 { return "0".to_string(); }
+
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn roman(mut n: usize) -> String {
+    if n == 0 { return "0".to_string(); }
+
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+        (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+
+    let mut numeral = String::new();
+    for (value, digits) in NUMERALS {
+        while n >= value {
+            numeral.push_str(digits);
+            n -= value;
+        }
+    }
+    numeral
+}
@@ -1,11 +1,9 @@
-mod layout;
-mod page;
-
 use printpdf::*;
 use std::fs::File;
 use std::io::BufWriter;
 
-use crate::page::PageViewBuilder;
+use cv::layout::{Align, VAlign};
+use cv::page::PageViewBuilder;
 
 fn main() {
     let page_width = 210.0;
@@ -27,23 +25,27 @@ fn main() {
         "assets/fonts/static/SometypeMono-BoldItalic.ttf",
         &doc,
     );
+    view_builder.named_color("accent", 1.0, 0.53, 0.0);
+    view_builder.detect_overwrites(true);
     let view = view_builder.build();
 
-    let current_page = doc.get_page(initial_page);
+    let title = "<h1><bo>Title</bo></h1> <color=accent>Subtitle";
+    let body = "<it>Lorem ipsum<fg> dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
 
-    view.draw_page(&current_page, |mut ctx| {
-        ctx.frame(|mut ctx| {
-            ctx.vsplit(1, |mut ctx| {
-                ctx.padding(1, 1, 0, 0, |mut ctx| {
-                    ctx.ftext("<h1><bo>Title");
-                });
-            }, |mut ctx| {
-                ctx.ftext("<it>Lorem ipsum<fg> dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.");
+    let (_, diagnostics) = view.draw_pages(&doc, initial_page, |mut ctx| {
+        ctx.frame_titled(title, Align::Left, |mut ctx| {
+            ctx.flow(|mut ctx| {
+                ctx.ftext_aligned(body, Align::Justify, VAlign::Top);
             });
         });
     });
 
     doc.save(&mut BufWriter::new(File::create("out.pdf").unwrap())).unwrap();
+
+    eprint!("{}", diagnostics);
+    if diagnostics.has_errors() {
+        std::process::exit(1);
+    }
 }
 
 
@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::page::{Symbol, Theme};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkupError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "markup error at position {}: {}", self.position, self.message)
+    }
+}
+
+impl Error for MarkupError {}
+
+pub fn parse(text: &str, theme: &Theme) -> Result<Vec<Symbol>, MarkupError> {
+    let chars: Vec<char> = text.chars().collect();
+    let base = Symbol {
+        character: ' ',
+        color: theme.fg,
+        bold: false,
+        italic: false,
+        background: None,
+    };
+
+    let mut symbols = Vec::new();
+    let mut stack: Vec<(String, Symbol)> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let style = stack.last().map(|(_, s)| *s).unwrap_or(base);
+
+        match chars[i] {
+            '\\' => {
+                let c = *chars.get(i + 1).ok_or_else(|| err(i, "escape sequence at end of input"))?;
+                symbols.push(Symbol { character: c, ..style });
+                i += 2;
+            }
+            '<' if chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) => {
+                let end = chars[i..].iter().position(|c| *c == '>').map(|end| i + end)
+                    .ok_or_else(|| err(i, "unterminated tag"))?;
+                let tag: String = chars[i + 1..end].iter().collect();
+
+                if let Some(name) = tag.strip_prefix('/') {
+                    let (open, _) = stack.pop().ok_or_else(|| err(i, format!("closing tag </{}> without matching opening tag", name)))?;
+                    if !name.is_empty() && name != open {
+                        return Err(err(i, format!("mismatched closing tag </{}>, expected </{}>", name, open)));
+                    }
+                } else {
+                    let style = apply(&tag, style, base, theme).map_err(|message| err(i, message))?;
+                    let name = tag.split('=').next().unwrap_or_default().to_string();
+                    stack.push((name, style));
+                }
+
+                i = end + 1;
+            }
+            c => {
+                symbols.push(Symbol { character: c, ..style });
+                i += 1;
+            }
+        }
+    }
+
+    Ok(symbols)
+}
+
+fn apply(tag: &str, style: Symbol, base: Symbol, theme: &Theme) -> Result<Symbol, String> {
+    let color = |color: &str| theme.resolve(color).ok_or_else(|| format!("unknown color '{}'", color));
+
+    Ok(match tag {
+        "fg" => base,
+        "h1" => Symbol { color: theme.h1, ..style },
+        "h2" => Symbol { color: theme.h2, ..style },
+        "h3" => Symbol { color: theme.h3, ..style },
+        "h4" => Symbol { color: theme.h4, ..style },
+        "bo" => Symbol { bold: true, ..style },
+        "it" => Symbol { italic: true, ..style },
+        "inv" => Symbol { color: style.background.unwrap_or(theme.bg), background: Some(style.color), ..style },
+        _ if tag.starts_with('#') => Symbol { color: color(tag)?, ..style },
+        _ => match tag.split_once('=') {
+            Some(("color", name)) => Symbol { color: color(name)?, ..style },
+            Some(("bg", name)) => Symbol { background: Some(color(name)?), ..style },
+            _ => return Err(format!("unknown tag <{}>", tag)),
+        },
+    })
+}
+
+fn err(position: usize, message: impl Into<String>) -> MarkupError {
+    MarkupError { position, message: message.into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> Theme {
+        Theme {
+            bg: (0.0, 0.0, 0.0),
+            fg: (1.0, 1.0, 1.0),
+            h1: (1.0, 0.0, 0.0),
+            h2: (0.0, 1.0, 0.0),
+            h3: (0.0, 0.0, 1.0),
+            h4: (1.0, 1.0, 0.0),
+            palette: [("accent".to_string(), (1.0, 0.5, 0.0))].into_iter().collect(),
+        }
+    }
+
+    fn text(symbols: &[Symbol]) -> String {
+        symbols.iter().map(|s| s.character).collect()
+    }
+
+    fn error(markup: &str) -> MarkupError {
+        parse(markup, &theme()).err().expect("expected a markup error")
+    }
+
+    #[test]
+    fn plain_text_uses_the_foreground() {
+        let symbols = parse("plain", &theme()).unwrap();
+        assert_eq!(text(&symbols), "plain");
+        assert!(symbols.iter().all(|s| s.color == (1.0, 1.0, 1.0) && !s.bold && s.background.is_none()));
+    }
+
+    #[test]
+    fn nested_tags_stack_and_unwind() {
+        let symbols = parse("<h1>a<bo>b</bo>c</h1>d", &theme()).unwrap();
+        assert_eq!(text(&symbols), "abcd");
+        assert_eq!(symbols[1].color, (1.0, 0.0, 0.0));
+        assert!(symbols[1].bold);
+        assert!(!symbols[2].bold);
+        assert_eq!(symbols[2].color, (1.0, 0.0, 0.0));
+        assert_eq!(symbols[3].color, (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn anonymous_closing_tag_pops_the_innermost() {
+        let symbols = parse("<it><h2>a</>b", &theme()).unwrap();
+        assert_eq!(symbols[1].color, (1.0, 1.0, 1.0));
+        assert!(symbols[1].italic);
+    }
+
+    #[test]
+    fn colors_resolve_hex_and_palette_names() {
+        let symbols = parse("<#f00>a</><color=accent>b</><bg=h3>c", &theme()).unwrap();
+        assert_eq!(symbols[0].color, (1.0, 0.0, 0.0));
+        assert_eq!(symbols[1].color, (1.0, 0.5, 0.0));
+        assert_eq!(symbols[2].background, Some((0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn escapes_are_taken_literally() {
+        assert_eq!(text(&parse("a\\<b\\>\\\\", &theme()).unwrap()), "a<b>\\");
+    }
+
+    #[test]
+    fn lone_angle_bracket_before_space_is_text() {
+        assert_eq!(text(&parse("a < b", &theme()).unwrap()), "a < b");
+    }
+
+    #[test]
+    fn trailing_escape_is_an_error() {
+        assert_eq!(error("ab\\").position, 2);
+    }
+
+    #[test]
+    fn unterminated_tag_is_an_error() {
+        let e = error("salary <5k");
+        assert_eq!(e.position, 7);
+        assert_eq!(e.message, "unterminated tag");
+    }
+
+    #[test]
+    fn mismatched_closing_tag_is_an_error() {
+        let e = error("<h1><bo>x</h1>");
+        assert_eq!(e.position, 9);
+        assert_eq!(e.message, "mismatched closing tag </h1>, expected </bo>");
+    }
+
+    #[test]
+    fn unmatched_closing_tag_is_an_error() {
+        assert_eq!(error("x</bo>").position, 1);
+    }
+
+    #[test]
+    fn unknown_tags_and_colors_are_errors() {
+        assert_eq!(error("<blink>x").message, "unknown tag <blink>");
+        assert_eq!(error("<color=nope>x").message, "unknown color 'nope'");
+    }
+}
@@ -0,0 +1,118 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Glyphs {
+    #[default]
+    Ascii,
+    Block,
+    Dots,
+    Custom(char, char),
+}
+
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+#[derive(Clone, Debug)]
+pub struct Meter {
+    glyphs: Glyphs,
+    filled: String,
+    empty: String,
+    brackets: bool,
+    percent: bool,
+    label_width: Option<usize>,
+}
+
+impl Meter {
+    pub fn new(glyphs: Glyphs) -> Self {
+        Self {
+            glyphs,
+            filled: "h1".to_string(),
+            empty: "fg".to_string(),
+            brackets: true,
+            percent: true,
+            label_width: None,
+        }
+    }
+
+    pub fn colors(mut self, filled: &str, empty: &str) -> Self {
+        self.filled = filled.to_string();
+        self.empty = empty.to_string();
+        self
+    }
+
+    pub fn brackets(mut self, enabled: bool) -> Self {
+        self.brackets = enabled;
+        self
+    }
+
+    pub fn percent(mut self, enabled: bool) -> Self {
+        self.percent = enabled;
+        self
+    }
+
+    pub fn label_width(mut self, width: usize) -> Self {
+        self.label_width = Some(width);
+        self
+    }
+
+    pub(crate) fn filled_color(&self) -> &str {
+        &self.filled
+    }
+
+    pub(crate) fn empty_color(&self) -> &str {
+        &self.empty
+    }
+
+    pub(crate) fn has_brackets(&self) -> bool {
+        self.brackets
+    }
+
+    pub(crate) fn has_percent(&self) -> bool {
+        self.percent
+    }
+
+    pub(crate) fn fixed_label_width(&self) -> Option<usize> {
+        self.label_width
+    }
+
+    pub(crate) fn glyphs(&self) -> Glyphs {
+        self.glyphs
+    }
+
+    pub(crate) fn ascii(&self) -> Self {
+        Self { glyphs: Glyphs::Ascii, ..self.clone() }
+    }
+
+    // every character a bar may use
+    pub(crate) fn chars(&self) -> Vec<char> {
+        let (full, empty) = self.full_and_empty();
+        let mut chars = vec![full, empty];
+        if self.glyphs == Glyphs::Block {
+            chars.extend(EIGHTHS);
+        }
+        chars
+    }
+
+    // the bar as (character, filled) pairs, only block glyphs get fractional cells
+    pub(crate) fn bar(&self, value: f32, width: usize) -> Vec<(char, bool)> {
+        let (full, empty) = self.full_and_empty();
+
+        if self.glyphs == Glyphs::Block {
+            let eighths = (value * width as f32 * 8.0).round() as usize;
+            (0..width).map(|i| match eighths.saturating_sub(i * 8).min(8) {
+                8 => (full, true),
+                0 => (empty, false),
+                n => (EIGHTHS[n - 1], true),
+            }).collect()
+        } else {
+            let cells = (value * width as f32).round() as usize;
+            (0..width).map(|i| if i < cells { (full, true) } else { (empty, false) }).collect()
+        }
+    }
+
+    fn full_and_empty(&self) -> (char, char) {
+        match self.glyphs {
+            Glyphs::Ascii => ('#', '-'),
+            Glyphs::Block => ('█', '░'),
+            Glyphs::Dots => ('●', '○'),
+            Glyphs::Custom(full, empty) => (full, empty),
+        }
+    }
+}
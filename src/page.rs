@@ -1,4 +1,5 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use printpdf::*;
 use std::fs::File;
 use std::io::Read;
@@ -7,7 +8,9 @@ use std::rc::Rc;
 use azul_text_layout::text_layout::{split_text_into_words, words_to_scaled_words};
 use azul_text_layout::text_shaping::get_font_metrics_freetype;
 
+use crate::diagnostics::{Diagnostic, Diagnostics, Severity};
 use crate::layout::LayoutContext;
+use crate::regions::Regions;
 
 pub struct PageView {
     font: IndirectFontRef,
@@ -22,48 +25,120 @@ pub struct PageView {
     view_symbol_height: usize,
     view_padding_width: f32,
     view_padding_height: f32,
-    bg_color: (f32, f32, f32),
-    fg_color: (f32, f32, f32),
-    h1_color: (f32, f32, f32),
-    h2_color: (f32, f32, f32),
-    h3_color: (f32, f32, f32),
-    h4_color: (f32, f32, f32)
+    symbol_width: f32,
+    symbol_height: f32,
+    detect_overwrites: bool,
+    debug_overlay: bool,
+    debug_rulers: bool,
+    glyphs: Rc<HashSet<char>>,
+    theme: Rc<Theme>
 }
 
 impl PageView {
-    pub(crate) fn draw_page(&self, page: &PdfPageReference, layout: fn(LayoutContext)) {
-        let bg = page.add_layer("bg");
-        let fg = page.add_layer("fg");
+    pub fn draw_page(&self, page: &PdfPageReference, layout: impl FnOnce(LayoutContext)) -> Diagnostics {
+        let buf = Rc::new(RefCell::new(self.blank_buffer()));
+        let pages = Rc::new(Cell::new(1));
+        let root = self.layout_context(buf.clone(), 0, pages.clone());
+        layout(root.clone());
+        self.render(page, &buf.borrow());
+        self.render_debug(page, &root.regions().borrow());
+
+        let mut diagnostics = root.diagnostics();
+        if pages.get() > 1 {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                path: "page".to_string(),
+                page: 0,
+                origin: (0, 0),
+                size: (self.view_symbol_width, self.view_symbol_height),
+                message: format!("flow runs over {} pages but only the first is drawn, use draw_pages", pages.get()),
+            });
+        }
+        diagnostics
+    }
+
+    pub fn draw_pages(&self, doc: &PdfDocumentReference, first_page: PdfPageIndex, mut layout: impl FnMut(LayoutContext)) -> (Vec<PdfPageIndex>, Diagnostics) {
+        let pages = Rc::new(Cell::new(1));
+        let mut indices = vec![first_page];
+        let mut diagnostics = Diagnostics::default();
+
+        let mut page = 0;
+        while page < pages.get() {
+            if page >= indices.len() {
+                let (index, _) = doc.add_page(Mm(self.page_width), Mm(self.page_height), "Layer 1");
+                indices.push(index);
+            }
+
+            let buf = Rc::new(RefCell::new(self.blank_buffer()));
+            let root = self.layout_context(buf.clone(), page, pages.clone());
+            layout(root.clone());
+            self.render(&doc.get_page(indices[page]), &buf.borrow());
+            self.render_debug(&doc.get_page(indices[page]), &root.regions().borrow());
+            diagnostics.extend(root.diagnostics());
 
-        let buf: Vec<Vec<Symbol>> = iter::repeat(iter::repeat(Symbol {
+            page += 1;
+        }
+
+        (indices, diagnostics)
+    }
+}
+
+impl PageView {
+    fn blank_buffer(&self) -> Vec<Vec<Symbol>> {
+        iter::repeat_n(iter::repeat_n(Symbol {
             character: ' ',
-            color: self.fg_color,
+            color: self.theme.fg,
             bold: false,
-            italic: false
-        }).take(self.view_symbol_width).collect()).take(self.view_symbol_height).collect();
-        let buf = Rc::new(RefCell::new(buf));
-        layout(LayoutContext::new(
-            buf.clone(),
+            italic: false,
+            background: None
+        }, self.view_symbol_width).collect(), self.view_symbol_height).collect()
+    }
+
+    fn layout_context(&self, buf: Rc<RefCell<Vec<Vec<Symbol>>>>, page: usize, pages: Rc<Cell<usize>>) -> LayoutContext {
+        let mut ctx = LayoutContext::new(
+            buf,
             (self.view_symbol_width, self.view_symbol_height),
             (0, 0),
             (self.view_symbol_width, self.view_symbol_height),
-            self.fg_color,
-            self.h1_color,
-            self.h2_color,
-            self.h3_color,
-            self.h4_color
-        ));
-
-        bg.set_fill_color(Color::Rgb(Rgb::new(self.bg_color.0, self.bg_color.1, self.bg_color.2, None)));
-
-        bg.add_polygon(Polygon {
-            rings: vec![vec![(Point::new(Mm(0.0), Mm(0.0)), false),
-                             (Point::new(Mm(self.page_width), Mm(0.0)), false),
-                             (Point::new(Mm(self.page_width), Mm(self.page_height)), false),
-                             (Point::new(Mm(0.0), Mm(self.page_height)), false)]],
-            mode: PolygonMode::Fill,
-            winding_order: WindingOrder::NonZero,
-        });
+            self.theme.clone(),
+            page,
+            pages
+        );
+        ctx.symbol_aspect(self.symbol_height / self.symbol_width);
+        ctx.font_glyphs(self.glyphs.clone());
+
+        if self.detect_overwrites {
+            ctx.detect_overwrites();
+        }
+
+        ctx
+    }
+
+    fn render(&self, page: &PdfPageReference, buf: &[Vec<Symbol>]) {
+        let bg = page.add_layer("bg");
+        let fg = page.add_layer("fg");
+
+        bg.set_fill_color(Color::Rgb(Rgb::new(self.theme.bg.0, self.theme.bg.1, self.theme.bg.2, None)));
+
+        bg.add_polygon(Self::rect(0.0, 0.0, self.page_width, self.page_height));
+
+        for (y, line) in buf.iter().enumerate() {
+            let mut x = 0;
+            while x < line.len() {
+                let Some(color) = line[x].background else {
+                    x += 1;
+                    continue;
+                };
+
+                let start = x;
+                while x < line.len() && line[x].background == Some(color) {
+                    x += 1;
+                }
+
+                bg.set_fill_color(Color::Rgb(Rgb::new(color.0, color.1, color.2, None)));
+                bg.add_polygon(self.cell_rect(start, x, y));
+            }
+        }
 
         fg.begin_text_section();
 
@@ -72,7 +147,7 @@ impl PageView {
         fg.set_character_spacing(self.character_spacing);
         fg.set_text_rendering_mode(TextRenderingMode::Fill);
 
-        for line in buf.borrow_mut().iter() {
+        for line in buf {
             for c in line {
                 fg.set_fill_color(Color::Rgb(Rgb::new(c.color.0, c.color.1, c.color.2, None)));
                 match (c.bold, c.italic) {
@@ -99,6 +174,117 @@ impl PageView {
 
         fg.end_text_section();
     }
+
+    fn render_debug(&self, page: &PdfPageReference, regions: &Regions) {
+        if !self.debug_overlay && !self.debug_rulers { return; }
+
+        let debug = page.add_layer("debug");
+        let label_size = self.font_size * 0.4;
+        debug.set_outline_thickness(0.5);
+
+        if self.debug_overlay {
+            for (i, region) in regions.iter().enumerate() {
+                let Some(((x, y), (w, h))) = region.visible else { continue; };
+
+                let (r, g, b) = Self::debug_color(i);
+                debug.set_outline_color(Color::Rgb(Rgb::new(r, g, b, None)));
+                debug.set_fill_color(Color::Rgb(Rgb::new(r, g, b, None)));
+
+                let (left, _) = self.cell_x(x);
+                let (_, right) = self.cell_x(x + w - 1);
+                let (_, top) = self.cell_y(y);
+                let (bottom, _) = self.cell_y(y + h - 1);
+
+                debug.add_line(Line {
+                    points: vec![(Point::new(Mm(left), Mm(bottom)), false),
+                                 (Point::new(Mm(right), Mm(bottom)), false),
+                                 (Point::new(Mm(right), Mm(top)), false),
+                                 (Point::new(Mm(left), Mm(top)), false)],
+                    is_closed: true,
+                });
+
+                let label = format!("{},{} {}x{}", x, y, w, h);
+                debug.use_text(label, label_size, Mm(left + 0.3), Mm(top) - Mm::from(Pt(label_size)), &self.font);
+            }
+        }
+
+        if self.debug_rulers {
+            debug.set_outline_color(Color::Rgb(Rgb::new(0.5, 0.5, 0.5, None)));
+            debug.set_fill_color(Color::Rgb(Rgb::new(0.5, 0.5, 0.5, None)));
+
+            let (_, top) = self.cell_y(0);
+            for x in (0..self.view_symbol_width).step_by(5) {
+                let (left, _) = self.cell_x(x);
+                let tick = if x % 10 == 0 { 1.5 } else { 0.75 };
+                debug.add_line(Line {
+                    points: vec![(Point::new(Mm(left), Mm(top)), false), (Point::new(Mm(left), Mm(top + tick)), false)],
+                    is_closed: false,
+                });
+                if x % 10 == 0 {
+                    debug.use_text(x.to_string(), label_size, Mm(left + 0.3), Mm(top + 1.0), &self.font);
+                }
+            }
+
+            let (left, _) = self.cell_x(0);
+            for y in (0..self.view_symbol_height).step_by(5) {
+                let (_, row_top) = self.cell_y(y);
+                let tick = if y % 10 == 0 { 1.5 } else { 0.75 };
+                debug.add_line(Line {
+                    points: vec![(Point::new(Mm(left), Mm(row_top)), false), (Point::new(Mm(left - tick), Mm(row_top)), false)],
+                    is_closed: false,
+                });
+                if y % 10 == 0 {
+                    debug.use_text(y.to_string(), label_size, Mm(left - self.view_padding_width + 1.0), Mm(row_top) - Mm::from(Pt(label_size)), &self.font);
+                }
+            }
+        }
+    }
+
+    fn debug_color(i: usize) -> (f32, f32, f32) {
+        let hue = (i as f32 * 0.618034).fract() * 6.0;
+        let x = 1.0 - (hue % 2.0 - 1.0).abs();
+        match hue as usize {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            _ => (1.0, 0.0, x),
+        }
+    }
+
+    fn cell_x(&self, x: usize) -> (f32, f32) {
+        let left = self.view_padding_width + x as f32 * self.symbol_width;
+        (left, left + self.symbol_width)
+    }
+
+    fn cell_y(&self, y: usize) -> (f32, f32) {
+        let baseline = self.page_height - Mm::from(Pt(self.font_size)).0 - self.view_padding_height - y as f32 * self.symbol_height;
+        let bottom = baseline - Mm::from(Pt(self.font_size)).0 * 0.2;
+        (bottom, bottom + self.symbol_height)
+    }
+
+    fn cell_rect(&self, start: usize, end: usize, y: usize) -> Polygon {
+        let (bottom, top) = self.cell_y(y);
+
+        Self::rect(
+            self.cell_x(start).0,
+            bottom,
+            self.cell_x(end).0,
+            top
+        )
+    }
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Polygon {
+        Polygon {
+            rings: vec![vec![(Point::new(Mm(x0), Mm(y0)), false),
+                             (Point::new(Mm(x1), Mm(y0)), false),
+                             (Point::new(Mm(x1), Mm(y1)), false),
+                             (Point::new(Mm(x0), Mm(y1)), false)]],
+            mode: PolygonMode::Fill,
+            winding_order: WindingOrder::NonZero,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -107,6 +293,49 @@ pub struct Symbol {
     pub color: (f32, f32, f32),
     pub bold: bool,
     pub italic: bool,
+    pub background: Option<(f32, f32, f32)>,
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub bg: (f32, f32, f32),
+    pub fg: (f32, f32, f32),
+    pub h1: (f32, f32, f32),
+    pub h2: (f32, f32, f32),
+    pub h3: (f32, f32, f32),
+    pub h4: (f32, f32, f32),
+    pub palette: HashMap<String, (f32, f32, f32)>,
+}
+
+impl Theme {
+    pub fn resolve(&self, color: &str) -> Option<(f32, f32, f32)> {
+        match color {
+            "bg" => Some(self.bg),
+            "fg" => Some(self.fg),
+            "h1" => Some(self.h1),
+            "h2" => Some(self.h2),
+            "h3" => Some(self.h3),
+            "h4" => Some(self.h4),
+            _ => match color.strip_prefix('#') {
+                Some(hex) => Self::parse_hex(hex),
+                None => self.palette.get(color).copied(),
+            },
+        }
+    }
+
+    fn parse_hex(hex: &str) -> Option<(f32, f32, f32)> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) { return None; }
+
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok().map(|v| v as f32 / 255.0);
+        match hex.len() {
+            3 => {
+                let expanded: String = hex.chars().flat_map(|c| [c, c]).collect();
+                Self::parse_hex(&expanded)
+            }
+            6 => Some((channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+            _ => None,
+        }
+    }
 }
 
 pub struct PageViewBuilder {
@@ -117,6 +346,7 @@ pub struct PageViewBuilder {
     page_padding: f32,
     symbol_width: Option<f32>,
     symbol_height: Option<f32>,
+    glyphs: Option<Rc<HashSet<char>>>,
     font: Option<IndirectFontRef>,
     font_bold: Option<IndirectFontRef>,
     font_italic: Option<IndirectFontRef>,
@@ -126,7 +356,11 @@ pub struct PageViewBuilder {
     h1_color: (f32, f32, f32),
     h2_color: (f32, f32, f32),
     h3_color: (f32, f32, f32),
-    h4_color: (f32, f32, f32)
+    h4_color: (f32, f32, f32),
+    palette: HashMap<String, (f32, f32, f32)>,
+    detect_overwrites: bool,
+    debug_overlay: bool,
+    debug_rulers: bool
 }
 
 impl PageViewBuilder {
@@ -139,6 +373,7 @@ impl PageViewBuilder {
             page_padding: 10.0,
             symbol_width: None,
             symbol_height: None,
+            glyphs: None,
             font: None,
             font_bold: None,
             font_italic: None,
@@ -148,7 +383,11 @@ impl PageViewBuilder {
             h1_color: (1.0, 0.0, 0.0),
             h2_color: (0.0, 1.0, 0.0),
             h3_color: (0.0, 0.0, 1.0),
-            h4_color: (1.0, 1.0, 0.0)
+            h4_color: (1.0, 1.0, 0.0),
+            palette: HashMap::new(),
+            detect_overwrites: false,
+            debug_overlay: false,
+            debug_rulers: false
         }
     }
 
@@ -176,6 +415,22 @@ impl PageViewBuilder {
         self.h4_color = (r, g, b);
     }
 
+    pub fn named_color(&mut self, name: &str, r: f32, g: f32, b: f32) {
+        self.palette.insert(name.to_string(), (r, g, b));
+    }
+
+    pub fn detect_overwrites(&mut self, enabled: bool) {
+        self.detect_overwrites = enabled;
+    }
+
+    pub fn debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = enabled;
+    }
+
+    pub fn debug_rulers(&mut self, enabled: bool) {
+        self.debug_rulers = enabled;
+    }
+
     pub fn page_size(&mut self, w: f32, h: f32) {
         self.page_width = w;
         self.page_height = h;
@@ -191,6 +446,7 @@ impl PageViewBuilder {
 
         self.symbol_width = Some(symbol_size.0);
         self.symbol_height = Some(symbol_size.1);
+        self.glyphs = Some(Rc::new(Self::font_glyphs(font)));
     }
 
     pub fn load_auxiliary_fonts(&mut self, font_bold: &str, font_italic: &str, font_bold_italic: &str, doc: &PdfDocumentReference) {
@@ -217,16 +473,31 @@ impl PageViewBuilder {
             view_symbol_height,
             view_padding_width,
             view_padding_height,
-            bg_color: self.bg_color,
-            fg_color: self.fg_color,
-            h1_color: self.h1_color,
-            h2_color: self.h2_color,
-            h3_color: self.h3_color,
-            h4_color: self.h4_color
+            symbol_width: self.symbol_width.expect("fonts required"),
+            symbol_height: self.symbol_height.expect("fonts required"),
+            detect_overwrites: self.detect_overwrites,
+            debug_overlay: self.debug_overlay,
+            debug_rulers: self.debug_rulers,
+            glyphs: self.glyphs.expect("fonts required"),
+            theme: Rc::new(Theme {
+                bg: self.bg_color,
+                fg: self.fg_color,
+                h1: self.h1_color,
+                h2: self.h2_color,
+                h3: self.h3_color,
+                h4: self.h4_color,
+                palette: self.palette
+            })
         }
     }
 }
 
+impl Default for PageViewBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PageViewBuilder {
     fn calculate_symbol_size(path: &str, font_size: f32, character_spacing: f32) -> (f32, f32) {
         let mut font = Vec::new();
@@ -239,6 +510,23 @@ impl PageViewBuilder {
         (Mm::from(Pt(font_width + character_spacing)).0, Mm::from(Pt(font_size + character_spacing)).0)
     }
 
+    fn font_glyphs(path: &str) -> HashSet<char> {
+        let mut font = Vec::new();
+        File::open(path).unwrap().read_to_end(&mut font).expect("Font file not found");
+
+        let mut glyphs = HashSet::new();
+        if let Ok(face) = ttf_parser::Face::from_slice(&font, 0) {
+            for subtable in face.character_mapping_subtables().filter(|subtable| subtable.is_unicode()) {
+                subtable.codepoints(|c| {
+                    if subtable.glyph_index(c).is_some() {
+                        glyphs.extend(char::from_u32(c));
+                    }
+                });
+            }
+        }
+        glyphs
+    }
+
     fn calculate_view_symbol_size(page_width: f32, page_height: f32, page_padding: f32, symbol_width: f32, symbol_height: f32) -> (usize, usize) {
         let view_symbol_width = ((page_width - 2.0 * page_padding) / symbol_width).floor() as usize;
         let view_symbol_height = ((page_height - 2.0 * page_padding) / symbol_height).floor() as usize;
@@ -0,0 +1,79 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+pub(crate) struct Region {
+    pub parent: Option<usize>,
+    pub path: Rc<str>,
+    pub visible: Option<((usize, usize), (usize, usize))>,
+}
+
+#[derive(Default)]
+pub(crate) struct Regions {
+    regions: Vec<Region>,
+    names: HashMap<(usize, String), usize>,
+}
+
+impl Regions {
+    pub fn register(&mut self, region: Region) -> usize {
+        self.regions.push(region);
+        self.regions.len() - 1
+    }
+
+    // repeated names under one parent get a sibling index, the first keeps the plain name
+    pub fn name(&mut self, parent: usize, name: &str) -> String {
+        let count = self.names.entry((parent, name.to_string())).or_default();
+        *count += 1;
+        if *count == 1 { name.to_string() } else { format!("{}[{}]", name, *count - 1) }
+    }
+
+    pub fn get(&self, id: usize) -> &Region {
+        &self.regions[id]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    pub fn is_ancestor(&self, ancestor: usize, mut id: usize) -> bool {
+        loop {
+            if id == ancestor { return true; }
+            match self.regions[id].parent {
+                Some(parent) => id = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+pub(crate) struct Ownership {
+    owners: Vec<Vec<Option<usize>>>,
+    reported: HashSet<(usize, usize)>,
+}
+
+impl Ownership {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            owners: vec![vec![None; width]; height],
+            reported: HashSet::new(),
+        }
+    }
+
+    pub fn claim(&mut self, x: usize, y: usize, region: usize, regions: &Regions) -> Option<usize> {
+        let owner = self.owners.get_mut(y)?.get_mut(x)?;
+        let Some(previous) = *owner else {
+            *owner = Some(region);
+            return None;
+        };
+
+        if regions.is_ancestor(region, previous) {
+            return None;
+        }
+
+        *owner = Some(region);
+        if regions.is_ancestor(previous, region) || !self.reported.insert((previous, region)) {
+            return None;
+        }
+
+        Some(previous)
+    }
+}
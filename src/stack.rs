@@ -0,0 +1,142 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Fixed(usize),
+    Percent(f32),
+    Flex(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Stack {
+    sizes: Vec<Size>,
+    separators: bool,
+    gutter: usize,
+}
+
+impl Stack {
+    pub fn new(sizes: &[Size]) -> Self {
+        Self {
+            sizes: sizes.to_vec(),
+            separators: false,
+            gutter: 0,
+        }
+    }
+
+    pub fn separators(mut self, enabled: bool) -> Self {
+        self.separators = enabled;
+        self
+    }
+
+    pub fn gutter(mut self, gutter: usize) -> Self {
+        self.gutter = gutter;
+        self
+    }
+
+    pub(crate) fn has_separators(&self) -> bool {
+        self.separators
+    }
+
+    pub(crate) fn gap(&self) -> usize {
+        if self.separators {
+            self.gutter * 2 + 1
+        } else {
+            self.gutter
+        }
+    }
+
+    pub(crate) fn separator_offset(&self) -> usize {
+        self.gutter
+    }
+
+    pub(crate) fn distribute(&self, length: usize) -> Vec<usize> {
+        let gaps = self.gap() * self.sizes.len().saturating_sub(1);
+        let available = length.saturating_sub(gaps);
+
+        let mut remaining = available;
+        let mut lengths: Vec<usize> = self.sizes.iter().map(|size| {
+            let wanted = match *size {
+                Size::Fixed(cells) => cells,
+                Size::Percent(percent) => (available as f32 * percent / 100.0).floor().max(0.0) as usize,
+                Size::Flex(_) => 0,
+            };
+            let taken = wanted.min(remaining);
+            remaining -= taken;
+            taken
+        }).collect();
+
+        // widened so huge lengths or weights cannot overflow, each share is at most remaining
+        let weights: u128 = self.sizes.iter().map(|size| match size {
+            Size::Flex(weight) => *weight as u128,
+            _ => 0,
+        }).sum();
+
+        let share = remaining as u128;
+        for (length, size) in lengths.iter_mut().zip(&self.sizes) {
+            if let Size::Flex(weight) = size {
+                *length = (share * *weight as u128).checked_div(weights).unwrap_or(0) as usize;
+                remaining -= *length;
+            }
+        }
+
+        for (length, size) in lengths.iter_mut().zip(&self.sizes) {
+            if remaining == 0 { break; }
+            if matches!(size, Size::Flex(weight) if *weight > 0) {
+                *length += 1;
+                remaining -= 1;
+            }
+        }
+
+        lengths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_and_percent_take_their_share() {
+        let stack = Stack::new(&[Size::Fixed(3), Size::Percent(50.0), Size::Flex(1)]);
+        assert_eq!(stack.distribute(10), [3, 5, 2]);
+    }
+
+    #[test]
+    fn flex_splits_by_weight() {
+        let stack = Stack::new(&[Size::Flex(1), Size::Flex(3)]);
+        assert_eq!(stack.distribute(8), [2, 6]);
+    }
+
+    #[test]
+    fn flex_remainder_goes_to_the_first_weighted_items() {
+        let stack = Stack::new(&[Size::Flex(1), Size::Flex(0), Size::Flex(1), Size::Flex(1)]);
+        assert_eq!(stack.distribute(8), [3, 0, 3, 2]);
+    }
+
+    #[test]
+    fn gaps_come_off_the_top() {
+        let stack = Stack::new(&[Size::Flex(1), Size::Flex(1)]).separators(true).gutter(1);
+        assert_eq!(stack.distribute(9), [3, 3]);
+    }
+
+    #[test]
+    fn fixed_items_never_exceed_the_length() {
+        let stack = Stack::new(&[Size::Fixed(6), Size::Fixed(6), Size::Flex(1)]);
+        assert_eq!(stack.distribute(8), [6, 2, 0]);
+    }
+
+    #[test]
+    fn zero_weights_take_nothing() {
+        let stack = Stack::new(&[Size::Fixed(2), Size::Flex(0)]);
+        assert_eq!(stack.distribute(5), [2, 0]);
+    }
+
+    #[test]
+    fn huge_lengths_and_weights_do_not_overflow() {
+        let stack = Stack::new(&[Size::Flex(usize::MAX), Size::Flex(usize::MAX)]);
+        let lengths = stack.distribute(usize::MAX);
+        assert_eq!(lengths.iter().map(|l| *l as u128).sum::<u128>(), usize::MAX as u128);
+        assert_eq!(lengths[0], lengths[1] + 1);
+
+        let stack = Stack::new(&[Size::Flex(1), Size::Flex(usize::MAX)]);
+        assert_eq!(stack.distribute(1 << 40)[1], (1 << 40) - 1);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::layout::Align;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Fixed(usize),
+    Percent(f32),
+    Auto,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TableCell {
+    pub text: String,
+    pub align: Option<Align>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Table {
+    columns: Vec<Column>,
+    aligns: Vec<Align>,
+    header: Option<Vec<TableCell>>,
+    header_style: String,
+    rows: Vec<Vec<TableCell>>,
+    borders: bool,
+}
+
+impl Table {
+    pub fn new(columns: &[Column]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            aligns: vec![Align::Left; columns.len()],
+            header: None,
+            header_style: "bo".to_string(),
+            rows: Vec::new(),
+            borders: false,
+        }
+    }
+
+    pub fn align(mut self, column: usize, align: Align) -> Self {
+        if let Some(a) = self.aligns.get_mut(column) {
+            *a = align;
+        }
+        self
+    }
+
+    pub fn borders(mut self, enabled: bool) -> Self {
+        self.borders = enabled;
+        self
+    }
+
+    pub fn header(mut self, cells: &[&str]) -> Self {
+        self.header = Some(Self::cells(cells));
+        self
+    }
+
+    pub fn header_style(mut self, tag: &str) -> Self {
+        self.header_style = tag.to_string();
+        self
+    }
+
+    pub fn row(mut self, cells: &[&str]) -> Self {
+        self.rows.push(Self::cells(cells));
+        self
+    }
+
+    pub fn cell_align(mut self, row: usize, column: usize, align: Align) -> Self {
+        if let Some(cell) = self.rows.get_mut(row).and_then(|r| r.get_mut(column)) {
+            cell.align = Some(align);
+        }
+        self
+    }
+
+    pub(crate) fn columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub(crate) fn has_borders(&self) -> bool {
+        self.borders
+    }
+
+    pub(crate) fn header_cells(&self) -> Option<&[TableCell]> {
+        self.header.as_deref()
+    }
+
+    pub(crate) fn header_markup(&self, text: &str) -> String {
+        if self.header_style.is_empty() {
+            text.to_string()
+        } else {
+            format!("<{}>{}</>", self.header_style, text)
+        }
+    }
+
+    pub(crate) fn rows(&self) -> &[Vec<TableCell>] {
+        &self.rows
+    }
+
+    pub(crate) fn column_align(&self, column: usize) -> Align {
+        self.aligns[column]
+    }
+
+    // cells are separated by " | " with borders and by two spaces without
+    pub(crate) fn gap(&self) -> usize {
+        if self.borders { 3 } else { 2 }
+    }
+
+    pub(crate) fn edge(&self) -> usize {
+        if self.borders { 2 } else { 0 }
+    }
+
+    pub(crate) fn widths(&self, width: usize, natural: &[usize]) -> Vec<usize> {
+        let gaps = self.gap() * self.columns.len().saturating_sub(1) + self.edge() * 2;
+        let available = width.saturating_sub(gaps);
+
+        let mut remaining = available;
+        let mut widths: Vec<usize> = self.columns.iter().map(|column| {
+            let wanted = match *column {
+                Column::Fixed(cells) => cells,
+                Column::Percent(percent) => (available as f32 * percent / 100.0).floor().max(0.0) as usize,
+                Column::Auto => 0,
+            };
+            let taken = wanted.min(remaining);
+            remaining -= taken;
+            taken
+        }).collect();
+
+        // narrow auto columns keep their natural width, the widest ones share what is left
+        let mut wanted: Vec<usize> = self.columns.iter().zip(natural)
+            .filter(|(column, _)| **column == Column::Auto)
+            .map(|(_, n)| *n)
+            .collect();
+        wanted.sort_unstable();
+
+        let mut cap = usize::MAX;
+        let mut share = remaining;
+        for (i, n) in wanted.iter().enumerate() {
+            let left = wanted.len() - i;
+            if n * left > share {
+                cap = share / left;
+                break;
+            }
+            share -= n;
+        }
+
+        for ((width, column), natural) in widths.iter_mut().zip(&self.columns).zip(natural) {
+            if *column == Column::Auto {
+                *width = (*natural).min(cap);
+            }
+        }
+
+        widths
+    }
+}
+
+impl Table {
+    fn cells(cells: &[&str]) -> Vec<TableCell> {
+        cells.iter().map(|text| TableCell { text: text.to_string(), align: None }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_columns_keep_their_natural_width_when_it_fits() {
+        let table = Table::new(&[Column::Auto, Column::Auto]);
+        assert_eq!(table.widths(20, &[3, 5]), [3, 5]);
+    }
+
+    #[test]
+    fn widest_auto_columns_share_what_is_left() {
+        let table = Table::new(&[Column::Auto, Column::Auto, Column::Auto]);
+        assert_eq!(table.widths(20, &[2, 30, 40]), [2, 7, 7]);
+    }
+
+    #[test]
+    fn auto_columns_are_capped_evenly_when_none_fit() {
+        let table = Table::new(&[Column::Auto, Column::Auto]);
+        assert_eq!(table.widths(12, &[10, 10]), [5, 5]);
+    }
+
+    #[test]
+    fn fixed_and_percent_columns_come_first() {
+        let table = Table::new(&[Column::Fixed(4), Column::Percent(50.0), Column::Auto]);
+        assert_eq!(table.widths(24, &[0, 0, 30]), [4, 10, 6]);
+    }
+
+    #[test]
+    fn fixed_columns_never_exceed_the_width() {
+        let table = Table::new(&[Column::Fixed(8), Column::Fixed(8), Column::Auto]);
+        assert_eq!(table.widths(14, &[0, 0, 5]), [8, 2, 0]);
+    }
+
+    #[test]
+    fn borders_take_their_cells() {
+        let table = Table::new(&[Column::Auto, Column::Auto]).borders(true);
+        assert_eq!(table.widths(17, &[20, 20]), [5, 5]);
+    }
+}
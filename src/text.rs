@@ -0,0 +1,214 @@
+use crate::page::Symbol;
+
+pub(crate) struct Line {
+    pub symbols: Vec<Symbol>,
+    pub last: bool,
+}
+
+pub(crate) fn wrap(symbols: &[Symbol], width: usize, hyphenate: bool) -> Vec<Line> {
+    let mut lines = Vec::new();
+    if width == 0 { return lines; }
+
+    let mut paragraphs: Vec<&[Symbol]> = symbols.split(|s| s.character == '\n').collect();
+    if paragraphs.len() > 1 && paragraphs.last().is_some_and(|p| p.is_empty()) {
+        paragraphs.pop();
+    }
+
+    for paragraph in paragraphs {
+        let mut line: Vec<Symbol> = Vec::new();
+        let mut wrapped = false;
+
+        for token in tokens(paragraph) {
+            if token[0].character.is_whitespace() {
+                if !(wrapped && line.is_empty()) {
+                    line.extend_from_slice(token);
+                }
+                continue;
+            }
+
+            let mut word = token;
+            while !word.is_empty() {
+                let avail = width.saturating_sub(line.len());
+
+                if word.len() <= avail {
+                    line.extend_from_slice(word);
+                    break;
+                }
+
+                if hyphenate {
+                    if let Some(at) = hyphenation_point(word, avail) {
+                        line.extend_from_slice(&word[..at]);
+                        line.push(Symbol { character: '-', ..word[at - 1] });
+                        word = &word[at..];
+                        lines.push(Line { symbols: std::mem::take(&mut line), last: false });
+                        wrapped = true;
+                        continue;
+                    }
+                }
+
+                if word.len() > width && line.is_empty() {
+                    line.extend_from_slice(&word[..width]);
+                    word = &word[width..];
+                }
+
+                trim_end(&mut line);
+                if !line.is_empty() {
+                    lines.push(Line { symbols: std::mem::take(&mut line), last: false });
+                }
+                wrapped = true;
+            }
+        }
+
+        trim_end(&mut line);
+        if !(wrapped && line.is_empty()) {
+            lines.push(Line { symbols: line, last: true });
+        } else if let Some(line) = lines.last_mut() {
+            line.last = true;
+        }
+    }
+
+    lines
+}
+
+fn tokens(symbols: &[Symbol]) -> impl Iterator<Item = &[Symbol]> {
+    let mut rest = symbols;
+    std::iter::from_fn(move || {
+        let first = rest.first()?;
+        let space = first.character.is_whitespace();
+        let len = rest.iter().position(|s| s.character.is_whitespace() != space).unwrap_or(rest.len());
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+        Some(token)
+    })
+}
+
+fn hyphenation_point(word: &[Symbol], avail: usize) -> Option<usize> {
+    if avail < 3 { return None; }
+
+    let at = avail - 1;
+    if word.len() < at + 2 { return None; }
+    if !word[at - 1].character.is_alphabetic() || !word[at].character.is_alphabetic() { return None; }
+
+    Some(at)
+}
+
+fn trim_end(line: &mut Vec<Symbol>) {
+    while line.last().is_some_and(|s| s.character.is_whitespace()) {
+        line.pop();
+    }
+}
+
+pub(crate) fn justify(line: &[Symbol], width: usize) -> Vec<Symbol> {
+    let indent = line.iter().take_while(|s| s.character.is_whitespace()).count();
+    let gaps: Vec<usize> = (indent + 1..line.len())
+        .filter(|i| line[*i].character.is_whitespace() && !line[*i - 1].character.is_whitespace())
+        .collect();
+
+    if gaps.is_empty() || line.len() >= width {
+        return line.to_vec();
+    }
+
+    let extra = width - line.len();
+    let mut justified = Vec::with_capacity(width);
+    let mut gap = 0;
+
+    for (i, symbol) in line.iter().enumerate() {
+        if gaps.get(gap) == Some(&i) {
+            let count = extra / gaps.len() + usize::from(gap < extra % gaps.len());
+            justified.extend(std::iter::repeat_n(*symbol, count));
+            gap += 1;
+        }
+        justified.push(*symbol);
+    }
+
+    justified
+}
+
+pub(crate) fn ellipsize(line: &mut Vec<Symbol>, width: usize) {
+    let Some(last) = line.last().copied() else { return; };
+
+    if line.len() >= width {
+        line.truncate(width.saturating_sub(1));
+    }
+    trim_end(line);
+    line.push(Symbol { character: '…', ..last });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(text: &str) -> Vec<Symbol> {
+        text.chars().map(|character| Symbol { character, color: (1.0, 1.0, 1.0), bold: false, italic: false, background: None }).collect()
+    }
+
+    fn string(symbols: &[Symbol]) -> String {
+        symbols.iter().map(|s| s.character).collect()
+    }
+
+    fn lines(text: &str, width: usize, hyphenate: bool) -> Vec<String> {
+        wrap(&symbols(text), width, hyphenate).iter().map(|line| string(&line.symbols)).collect()
+    }
+
+    #[test]
+    fn wraps_on_word_boundaries() {
+        assert_eq!(lines("the quick brown fox", 10, false), ["the quick", "brown fox"]);
+    }
+
+    #[test]
+    fn marks_last_line_of_each_paragraph() {
+        let wrapped = wrap(&symbols("one two\nthree"), 5, false);
+        let last: Vec<bool> = wrapped.iter().map(|line| line.last).collect();
+        assert_eq!(last, [false, true, true]);
+    }
+
+    #[test]
+    fn keeps_empty_paragraphs() {
+        assert_eq!(lines("a\n\nb", 5, false), ["a", "", "b"]);
+    }
+
+    #[test]
+    fn hard_breaks_long_words() {
+        assert_eq!(lines("abcdefghij", 4, false), ["abcd", "efgh", "ij"]);
+        assert_eq!(lines("ab abcdefghij", 4, false), ["ab", "abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn hyphenates_between_letters() {
+        assert_eq!(lines("go extraordinary", 8, true), ["go extr-", "aordina-", "ry"]);
+    }
+
+    #[test]
+    fn hyphenates_only_letters() {
+        assert_eq!(lines("go 12345", 6, true), ["go", "12345"]);
+    }
+
+    #[test]
+    fn zero_width_yields_no_lines() {
+        assert!(wrap(&symbols("text"), 0, false).is_empty());
+    }
+
+    #[test]
+    fn justify_spreads_extra_space_from_the_left() {
+        assert_eq!(string(&justify(&symbols("a b c"), 8)), "a   b  c");
+    }
+
+    #[test]
+    fn justify_keeps_single_words() {
+        assert_eq!(string(&justify(&symbols("word"), 8)), "word");
+    }
+
+    #[test]
+    fn ellipsize_replaces_the_last_character() {
+        let mut line = symbols("abcdef");
+        ellipsize(&mut line, 6);
+        assert_eq!(string(&line), "abcde…");
+    }
+
+    #[test]
+    fn ellipsize_trims_trailing_space() {
+        let mut line = symbols("abc ");
+        ellipsize(&mut line, 8);
+        assert_eq!(string(&line), "abc…");
+    }
+}
@@ -0,0 +1,75 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32) -> Self {
+        Self { year, month: month.clamp(1, 12) }
+    }
+
+    pub fn year(year: i32) -> Self {
+        Self::new(year, 1)
+    }
+
+    pub(crate) fn years(self) -> f32 {
+        self.year as f32 + (self.month - 1) as f32 / 12.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub start: Date,
+    pub end: Date,
+    pub label: String,
+    pub color: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    entries: Vec<Entry>,
+    range: Option<(i32, i32)>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entry(mut self, start: Date, end: Date, label: &str, color: &str) -> Self {
+        self.entries.push(Entry {
+            start: start.min(end),
+            end: start.max(end),
+            label: label.to_string(),
+            color: color.to_string(),
+        });
+        self
+    }
+
+    pub fn range(mut self, from: i32, to: i32) -> Self {
+        let (from, to) = (from.min(to), from.max(to));
+        self.range = Some((from, to.max(from + 1)));
+        self
+    }
+
+    pub(crate) fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    // the axis covers whole years, ending after the year of the last entry
+    pub(crate) fn years(&self) -> (i32, i32) {
+        self.range.unwrap_or_else(|| {
+            let from = self.entries.iter().map(|e| e.start.year).min().unwrap_or(0);
+            let to = self.entries.iter().map(|e| e.end.year + 1).max().unwrap_or(1);
+            (from, to.max(from + 1))
+        })
+    }
+
+    // a cell spacing between year labels wide enough to fit them
+    pub(crate) fn label_step(&self, cells_per_year: f32) -> i32 {
+        let (from, to) = self.years();
+        let widest = from.to_string().len().max(to.to_string().len()) as f32 + 1.0;
+        ((widest / cells_per_year).ceil() as i32).max(1)
+    }
+}